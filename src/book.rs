@@ -0,0 +1,800 @@
+use clap::ValueEnum;
+use epub::doc::{DocError, EpubDoc, NavPoint};
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Component, Path, PathBuf};
+
+use crate::Error;
+
+/// An EPUB opened from disk
+pub type Book = EpubDoc<BufReader<File>>;
+
+/// Handling of empty spine documents such as section dividers
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyPages {
+    /// Show every spine document as its own page
+    #[default]
+    Keep,
+    /// Keep empty pages but step over them with Left/Right
+    Skip,
+    /// Fold empty pages into their neighbour so they disappear from the page count
+    Merge,
+}
+
+/// How a line of extracted text should be presented
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineKind {
+    #[default]
+    Body,
+    /// Placeholder standing in for an image inside a `<figure>`
+    Figure,
+    /// Text of a `<figcaption>`, placed directly beneath its figure
+    Caption,
+    /// Text of an `<h1>`-`<h6>` heading
+    Heading,
+}
+
+/// Extracted text of a spine document along with how each line should be presented
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Page {
+    pub text: String,
+    kinds: Vec<LineKind>,
+    /// Links in the order they appear, numbered from 1 in the text
+    pub links: Vec<Link>,
+    /// Line on which each element with an `id` starts
+    anchors: HashMap<String, usize>,
+    /// Words outside of `<nav>` elements, pages without any only hold a table of contents
+    content_words: usize,
+    /// Archive path of the first image in the document, shown instead of the text on pages
+    /// that have none, such as the pages of a comic
+    pub image: Option<String>,
+}
+
+/// A hyperlink found in a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    pub label: String,
+    pub href: String,
+    /// Spine document an internal link points at, if it could be resolved
+    pub spine_index: Option<usize>,
+    /// Byte offset of the link's `[n]` marker in the text of its page
+    pub marker: usize,
+}
+
+impl Link {
+    /// Links to websites or email addresses rather than another part of the book
+    pub fn is_external(&self) -> bool {
+        self.href.contains("://") || self.href.starts_with("mailto:")
+    }
+
+    /// The `#fragment` of the link, naming an element in the target document
+    pub fn anchor(&self) -> Option<&str> {
+        self.href
+            .split_once('#')
+            .map(|(_, anchor)| anchor)
+            .filter(|anchor| !anchor.is_empty())
+    }
+}
+
+impl Page {
+    /// Builds a page from chunks of text, chunks spanning several lines share their kind
+    fn from_chunks(chunks: Vec<(String, LineKind)>) -> Self {
+        let mut kinds = Vec::new();
+        for (chunk, kind) in &chunks {
+            kinds.extend(chunk.split('\n').map(|_| *kind));
+        }
+        let text = chunks
+            .into_iter()
+            .map(|(chunk, _)| chunk)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Page {
+            text,
+            kinds,
+            ..Page::default()
+        }
+    }
+
+    /// Kind of the line at `index` of `text`
+    pub fn kind(&self, index: usize) -> LineKind {
+        self.kinds.get(index).copied().unwrap_or_default()
+    }
+
+    /// Whether the page has fewer than `min_words` words outside of navigation,
+    /// empty and navigation-only pages always count as filler
+    pub fn is_filler(&self, min_words: usize) -> bool {
+        self.content_words < min_words.max(1)
+    }
+
+    /// Line of `text` on which the element with the given `id` starts
+    pub fn anchor_line(&self, id: &str) -> Option<usize> {
+        self.anchors.get(id).copied()
+    }
+
+    /// Adds the text of `other` after this page's, separated by a blank line
+    /// Its lines, anchors and link numbers are moved along to where its text now starts
+    fn append(&mut self, other: Page) {
+        let start = if self.text.is_empty() {
+            0
+        } else if is_blank(&other.text) {
+            // Nothing to add, anchors of an empty page point at the end of this one
+            self.text.lines().count().saturating_sub(1)
+        } else {
+            let blank_line = self.text.lines().count();
+            self.text.push_str("\n\n");
+            self.kinds.resize(blank_line + 1, LineKind::Body);
+            blank_line + 1
+        };
+        // Links are numbered from 1 on each page, renumber the markers after this page's links
+        // where they were recorded, text that merely looks like a marker is left alone
+        let mut text = other.text;
+        let mut links = other.links;
+        let mut shift = 0isize;
+        for (number, link) in links.iter_mut().enumerate() {
+            let marker = format!("[{}]", number + 1);
+            let renumbered = format!("[{}]", number + 1 + self.links.len());
+            let position = link.marker.saturating_add_signed(shift);
+            if text.get(position..position + marker.len()) == Some(marker.as_str()) {
+                text.replace_range(position..position + marker.len(), &renumbered);
+                shift += renumbered.len() as isize - marker.len() as isize;
+            }
+            link.marker = position;
+        }
+        let offset = self.text.len();
+        if !is_blank(&text) {
+            self.text.push_str(&text);
+            self.kinds.extend(other.kinds);
+        }
+        for link in &mut links {
+            link.marker += offset;
+        }
+        self.links.extend(links);
+        for (id, line) in other.anchors {
+            self.anchors.entry(id).or_insert(start + line);
+        }
+        self.content_words += other.content_words;
+        self.image = self.image.take().or(other.image);
+    }
+
+    /// The block of text starting at `line`, up to the next blank line
+    pub fn block_at(&self, line: usize) -> String {
+        self.text
+            .lines()
+            .skip(line)
+            .skip_while(|line| line.trim().is_empty())
+            .take_while(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// An entry of the table of contents, flattened with its nesting depth
+#[derive(Debug, Clone)]
+pub struct TocEntry {
+    pub label: String,
+    pub depth: usize,
+    /// Spine document the entry points at, if it could be resolved
+    pub spine_index: Option<usize>,
+}
+
+/// Flattens the book's table of contents, entries without a label are named "Chapter N"
+pub fn toc_entries<R: io::Read + io::Seek>(epub: &EpubDoc<R>) -> Vec<TocEntry> {
+    fn flatten<R: io::Read + io::Seek>(
+        epub: &EpubDoc<R>,
+        points: &[NavPoint],
+        depth: usize,
+        entries: &mut Vec<TocEntry>,
+    ) {
+        for point in points {
+            // Links into a chapter carry an #anchor that isn't part of the resource path
+            let content = point.content.to_string_lossy();
+            let path = PathBuf::from(content.split('#').next().unwrap_or_default());
+            let label = point.label.trim();
+            let label = if label.is_empty() {
+                format!("Chapter {}", entries.len() + 1)
+            } else {
+                label.to_string()
+            };
+            entries.push(TocEntry {
+                label,
+                depth,
+                spine_index: epub.resource_uri_to_chapter(&path),
+            });
+            flatten(epub, &point.children, depth + 1, entries);
+        }
+    }
+
+    let mut entries = Vec::new();
+    flatten(epub, &epub.toc, 0, &mut entries);
+    entries
+}
+
+/// The book's metadata with surrounding whitespace trimmed, leaving out empty values
+/// and fields without any
+pub fn metadata<R: io::Read + io::Seek>(epub: &EpubDoc<R>) -> HashMap<String, Vec<String>> {
+    epub.metadata
+        .iter()
+        .map(|(field, values)| {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect();
+            (field.clone(), values)
+        })
+        .filter(|(_, values)| !values.is_empty())
+        .collect()
+}
+
+/// Opens the EPUB at `path`
+pub fn open(path: &str) -> Result<Book, Error> {
+    EpubDoc::new(path).map_err(|error| match error {
+        DocError::IOError(source) => Error::OpenFailed {
+            path: path.to_string(),
+            source,
+        },
+        _ => Error::NotAnEpub {
+            path: path.to_string(),
+        },
+    })
+}
+
+/// Extracts the text of the spine document at `page`
+pub fn extract_page(book: &mut Book, path: &str, page: usize) -> Result<Page, Error> {
+    let failed = || Error::ExtractFailed {
+        path: path.to_string(),
+        page,
+    };
+    if !book.set_current_page(page) {
+        return Err(failed());
+    }
+    let (xhtml, _mime) = book.get_current_str().ok_or_else(failed)?;
+    let mut extracted = extract_page_from_xhtml(&xhtml);
+    let current = book.get_current_path().unwrap_or_default();
+    for link in &mut extracted.links {
+        link.spine_index = resolve_link(book, &current, link);
+    }
+    extracted.image = extracted
+        .image
+        .map(|src| resolve_path(&current, &src).to_string_lossy().into_owned());
+    Ok(extracted)
+}
+
+/// Spine index of the document an internal link points at
+/// `current` is the path of the document containing the link, which relative links start from
+fn resolve_link(book: &Book, current: &Path, link: &Link) -> Option<usize> {
+    if link.is_external() {
+        return None;
+    }
+    let file = link.href.split('#').next().unwrap_or_default();
+    if file.is_empty() {
+        return book.resource_uri_to_chapter(&current.to_path_buf());
+    }
+    book.resource_uri_to_chapter(&resolve_path(current, file))
+}
+
+/// Archive path of a file referenced from the document at `current`
+fn resolve_path(current: &Path, file: &str) -> PathBuf {
+    let mut path = current.parent().map(Path::to_path_buf).unwrap_or_default();
+    for component in Path::new(file).components() {
+        match component {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::Normal(part) => path.push(part),
+            _ => {}
+        }
+    }
+    path
+}
+
+pub fn extract_text_from_xhtml(xhtml: &str) -> String {
+    extract_page_from_xhtml(xhtml).text
+}
+
+/// Extracts the text of a document, one line per block element with blank lines between blocks
+/// Figures keep their captions directly beneath them
+pub fn extract_page_from_xhtml(xhtml: &str) -> Page {
+    let document = Html::parse_document(xhtml);
+
+    // Select the body of the HTML document
+    let selector = Selector::parse("body").unwrap();
+    let mut blocks = Blocks::default();
+
+    for element in document.select(&selector) {
+        collect_blocks(element, &mut blocks);
+    }
+    blocks.flush();
+
+    // Elements at the very end of the page have no text of their own to start on
+    let last_line = blocks.lines.saturating_sub(1);
+    for id in blocks.pending_anchors.drain(..) {
+        blocks.anchors.entry(id).or_insert(last_line);
+    }
+    Page {
+        links: blocks.links,
+        anchors: blocks.anchors,
+        content_words: blocks.content_words,
+        image: first_image(&document),
+        ..Page::from_chunks(blocks.chunks)
+    }
+}
+
+/// Source of the first `<img>`, or `<image>` inside an `<svg>`, in the body of a document
+fn first_image(document: &Html) -> Option<String> {
+    let images = Selector::parse("body img, body image").unwrap();
+    document
+        .select(&images)
+        .find_map(|image| {
+            // SVG images use `xlink:href`, which `attr` doesn't find outside its namespace
+            image
+                .value()
+                .attrs()
+                .find(|(name, _)| *name == "src" || *name == "href")
+                .map(|(_, src)| src)
+        })
+        .map(str::to_string)
+}
+
+/// Elements that start a new paragraph of text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "footer",
+    "header",
+    "hr",
+    "p",
+    "section",
+    "table",
+    "tr",
+];
+
+/// Elements whose content is never shown
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style"];
+
+/// Builds the lines of a page, gathering inline text until the end of its block
+#[derive(Default)]
+struct Blocks {
+    chunks: Vec<(String, LineKind)>,
+    line: String,
+    kind: LineKind,
+    /// A blank line goes before the next text because a block ended
+    separate: bool,
+    /// Number of lines in `chunks`
+    lines: usize,
+    /// Length of the text in `chunks` once they are joined by newlines
+    bytes: usize,
+    links: Vec<Link>,
+    /// Links whose marker is on the current line, with the marker's byte offset in the line
+    line_links: Vec<(usize, usize)>,
+    anchors: HashMap<String, usize>,
+    /// Ids of elements that start on the next line of text
+    pending_anchors: Vec<String>,
+    /// Number of `<nav>` elements the text being gathered is inside of
+    nav_depth: usize,
+    content_words: usize,
+    /// Lists the text being gathered is inside of, with the next number of each numbered one
+    lists: Vec<Option<usize>>,
+    /// Bullet or number that goes before the next text, for a list item that has none yet
+    marker: Option<String>,
+}
+
+impl Blocks {
+    /// Adds inline text, collapsing runs of whitespace like a browser would
+    fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            if !c.is_whitespace() {
+                if self.line.is_empty() {
+                    if let Some(marker) = self.marker.take() {
+                        self.line.push_str(&marker);
+                    }
+                }
+                self.line.push(c);
+            } else if !self.line.is_empty() && !self.line.ends_with(' ') {
+                self.line.push(' ');
+            }
+        }
+    }
+
+    /// Ends the current line, skipping it if it holds no text
+    fn flush(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        let line = line.trim_end();
+        if !line.is_empty() {
+            self.push(line.to_string(), self.kind);
+            let start = self.bytes - line.len();
+            for (index, column) in self.line_links.drain(..) {
+                self.links[index].marker = start + column;
+            }
+        }
+    }
+
+    /// Adds a finished chunk of text, after a blank line if a block ended before it
+    fn push(&mut self, chunk: String, kind: LineKind) {
+        if self.separate && !self.chunks.is_empty() {
+            self.chunks.push((String::new(), LineKind::Body));
+            self.lines += 1;
+            self.bytes += 1;
+        }
+        self.separate = false;
+        for id in self.pending_anchors.drain(..) {
+            self.anchors.entry(id).or_insert(self.lines);
+        }
+        self.lines += chunk.split('\n').count();
+        self.bytes += chunk.len() + usize::from(!self.chunks.is_empty());
+        if self.nav_depth == 0 {
+            self.content_words += chunk.split_whitespace().count();
+        }
+        self.chunks.push((chunk, kind));
+    }
+
+    /// Ends the current block so the next text starts a new paragraph
+    /// Lists are kept tight, blocks inside them only start a new line
+    fn end_block(&mut self) {
+        self.flush();
+        self.separate |= self.lists.is_empty();
+    }
+
+    /// Starts a list item on a new line, its text is indented by nesting depth
+    /// and prefixed with a bullet or number
+    fn start_item(&mut self, value: Option<usize>) {
+        self.end_block();
+        let depth = self.lists.len().saturating_sub(1);
+        let marker = match self.lists.last_mut() {
+            Some(Some(number)) => {
+                *number = value.unwrap_or(*number);
+                let marker = format!("{}. ", number);
+                *number += 1;
+                marker
+            }
+            Some(None) | None => "\u{2022} ".to_string(),
+        };
+        self.marker = Some(format!("{}{}", "  ".repeat(depth), marker));
+    }
+}
+
+/// Whether an element is an `h1`-`h6` heading
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// Walks an element collecting its text into blocks, figures are handled by `collect_figure`
+fn collect_blocks(element: ElementRef, blocks: &mut Blocks) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => blocks.push_text(text),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let name = child.value().name();
+                if let Some(id) = child.value().id() {
+                    // Text already gathered on the current line is where the element starts
+                    if blocks.line.is_empty() {
+                        blocks.pending_anchors.push(id.to_string());
+                    } else {
+                        let line = blocks.lines + usize::from(blocks.separate && blocks.lines > 0);
+                        blocks.anchors.entry(id.to_string()).or_insert(line);
+                    }
+                }
+                match name {
+                    _ if HIDDEN_ELEMENTS.contains(&name) => {}
+                    "a" if child.value().attr("href").is_some() => {
+                        collect_blocks(child, blocks);
+                        blocks.links.push(Link {
+                            label: single_line_text(child),
+                            href: child.value().attr("href").unwrap_or_default().to_string(),
+                            spine_index: None,
+                            marker: 0,
+                        });
+                        let marker = format!("[{}]", blocks.links.len());
+                        blocks.push_text(&marker);
+                        let column = blocks.line.len() - marker.len();
+                        blocks.line_links.push((blocks.links.len() - 1, column));
+                    }
+                    "figure" => collect_figure(child, blocks),
+                    // A caption outside of a figure has nothing to sit under
+                    "figcaption" => {
+                        blocks.end_block();
+                        blocks.push(single_line_text(child), LineKind::Caption);
+                        blocks.end_block();
+                    }
+                    "br" => blocks.flush(),
+                    "pre" => {
+                        blocks.end_block();
+                        let text: String = child.text().collect();
+                        let text = tidy_preformatted(&text);
+                        if !text.is_empty() {
+                            blocks.push(text, LineKind::Body);
+                        }
+                        blocks.end_block();
+                    }
+                    "nav" => {
+                        blocks.end_block();
+                        blocks.nav_depth += 1;
+                        collect_blocks(child, blocks);
+                        blocks.flush();
+                        blocks.nav_depth -= 1;
+                        blocks.end_block();
+                    }
+                    "ul" | "ol" => {
+                        blocks.end_block();
+                        collect_list(child, blocks);
+                        blocks.end_block();
+                    }
+                    "li" => {
+                        let value = child
+                            .value()
+                            .attr("value")
+                            .and_then(|value| value.parse().ok());
+                        blocks.start_item(value);
+                        collect_blocks(child, blocks);
+                        blocks.marker = None;
+                        blocks.end_block();
+                    }
+                    _ if is_heading(name) => {
+                        blocks.end_block();
+                        blocks.kind = LineKind::Heading;
+                        collect_blocks(child, blocks);
+                        blocks.flush();
+                        blocks.kind = LineKind::Body;
+                        blocks.end_block();
+                    }
+                    _ if BLOCK_ELEMENTS.contains(&name) => {
+                        blocks.end_block();
+                        collect_blocks(child, blocks);
+                        blocks.end_block();
+                    }
+                    _ => collect_blocks(child, blocks),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Walks a `<ul>` or `<ol>`, numbering the items of an ordered list from its `start` attribute
+fn collect_list(list: ElementRef, blocks: &mut Blocks) {
+    let ordered = list.value().name() == "ol";
+    let start = list
+        .value()
+        .attr("start")
+        .and_then(|start| start.parse().ok());
+    blocks.lists.push(ordered.then(|| start.unwrap_or(1)));
+    collect_blocks(list, blocks);
+    blocks.lists.pop();
+}
+
+/// Emits a placeholder for each image of a figure, then its caption, then any other text
+fn collect_figure(figure: ElementRef, blocks: &mut Blocks) {
+    blocks.end_block();
+    let images = Selector::parse("img, image, svg").unwrap();
+    let mut markers: Vec<String> = figure
+        .select(&images)
+        .map(|image| match image.value().attr("alt") {
+            Some(alt) if !alt.trim().is_empty() => format!("[Figure: {}]", alt.trim()),
+            _ => "[Figure]".to_string(),
+        })
+        .collect();
+    if markers.is_empty() {
+        markers.push("[Figure]".to_string());
+    }
+    for marker in markers {
+        blocks.push(marker, LineKind::Figure);
+    }
+
+    let captions = Selector::parse("figcaption").unwrap();
+    for caption in figure.select(&captions) {
+        blocks.push(single_line_text(caption), LineKind::Caption);
+    }
+
+    for child in figure.children() {
+        match child.value() {
+            Node::Text(text) => blocks.push_text(text),
+            Node::Element(element) if element.name() != "figcaption" => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_blocks(child, blocks);
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks.end_block();
+}
+
+/// Cleans up the text of a `<pre>` while keeping its line structure
+/// Tabs become spaces, trailing whitespace is trimmed, runs of three or more blank lines
+/// collapse to one and indentation shared by every line, left over from pretty-printed markup,
+/// is removed. Shorter runs of blank lines are kept as intentional breaks
+fn tidy_preformatted(text: &str) -> String {
+    let text = text.replace('\t', "    ");
+    let mut lines: Vec<&str> = Vec::new();
+    let mut blank_run = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank_run += 1;
+            continue;
+        }
+        // Blank lines before the first line of text are dropped
+        if !lines.is_empty() {
+            let blank_lines = if blank_run >= 3 { 1 } else { blank_run };
+            lines.extend(std::iter::repeat_n("", blank_lines));
+        }
+        blank_run = 0;
+        lines.push(line);
+    }
+    let leading_whitespace = |line: &str| line.chars().take_while(|c| c.is_whitespace()).count();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| leading_whitespace(line))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| match line.char_indices().nth(indent) {
+            Some((start, _)) => &line[start..],
+            None => "",
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of an element collapsed onto a single line
+fn single_line_text(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Checks the package document for `page-progression-direction="rtl"` on the spine
+pub fn has_rtl_page_progression<R: io::Read + io::Seek>(epub: &mut EpubDoc<R>) -> bool {
+    let root_file = epub.root_file.clone();
+    let Some(opf) = epub.get_resource_str_by_path(root_file) else {
+        return false;
+    };
+    let document = Html::parse_document(&opf);
+    let selector = Selector::parse("spine").unwrap();
+    document
+        .select(&selector)
+        .any(|spine| spine.value().attr("page-progression-direction") == Some("rtl"))
+}
+
+/// Returns true if a page has no visible text
+pub fn is_blank(text: &str) -> bool {
+    text.trim().is_empty()
+}
+
+/// Folds empty pages, and pages that are filler by `Page::is_filler`, into the preceding page
+/// Returns the remaining pages and, for each of them, the index of the first spine document it covers
+pub fn merge_empty_pages(content: Vec<Page>, min_words: usize) -> (Vec<Page>, Vec<usize>) {
+    let mut pages: Vec<Page> = Vec::new();
+    let mut spine_pages = Vec::new();
+    for (spine_index, page) in content.into_iter().enumerate() {
+        // Leading filler pages are kept on top of the first page with content
+        let leading_filler = pages.len() == 1 && pages[0].is_filler(min_words);
+        match pages.last_mut() {
+            Some(previous) if page.is_filler(min_words) || leading_filler => previous.append(page),
+            _ => {
+                pages.push(page);
+                spine_pages.push(spine_index);
+            }
+        }
+    }
+    (pages, spine_pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(body: &str) -> Page {
+        extract_page_from_xhtml(&format!("<html><body>{}</body></html>", body))
+    }
+
+    #[test]
+    fn preformatted_text_loses_shared_indentation_and_long_blank_runs() {
+        let text =
+            "\n  \u{a0}fn main() {\t\n\u{a0}      body();\n\n\n\n \u{a0} }\n\n \u{a0} end\n\n";
+        assert_eq!(
+            tidy_preformatted(text),
+            "fn main() {\n    body();\n\n}\n\nend"
+        );
+    }
+
+    #[test]
+    fn merging_folds_an_empty_spine_document_into_the_previous_page() {
+        let content = vec![
+            page("<p>Chapter one</p>"),
+            page("  \n "),
+            page("<p>Chapter two</p>"),
+        ];
+        let (pages, spine_pages) = merge_empty_pages(content, 1);
+
+        assert_eq!(spine_pages, vec![0, 2]);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].text, "Chapter one");
+        assert_eq!(pages[1].text, "Chapter two");
+    }
+
+    #[test]
+    fn merging_keeps_the_text_links_and_anchors_of_short_pages() {
+        let content = vec![
+            page("<p>The first chapter has <a href='#a'>a link</a> in it.</p>"),
+            page("<h2 id='part'>Part two</h2><p><a href='#b'>Next</a></p>"),
+            page("<p>The second chapter is long enough to keep.</p>"),
+        ];
+        let (pages, spine_pages) = merge_empty_pages(content, 4);
+
+        assert_eq!(spine_pages, vec![0, 2]);
+        let merged = &pages[0];
+        assert_eq!(
+            merged.text,
+            "The first chapter has a link[1] in it.\n\nPart two\n\nNext[2]"
+        );
+        assert_eq!(merged.kind(2), LineKind::Heading);
+        assert_eq!(merged.anchor_line("part"), Some(2));
+        assert_eq!(merged.links[1].href, "#b");
+    }
+
+    #[test]
+    fn figures_are_followed_by_their_captions() {
+        let page = page(
+            "<figure><figcaption>Cap</figcaption><img src='a.png' alt='x'/></figure><p>After</p>",
+        );
+        assert_eq!(page.text, "[Figure: x]\nCap\n\nAfter");
+        assert_eq!(page.kind(0), LineKind::Figure);
+        assert_eq!(page.kind(1), LineKind::Caption);
+        assert_eq!(page.kind(3), LineKind::Body);
+    }
+
+    #[test]
+    fn pages_remember_their_first_image() {
+        let comic = page("<div><img src='../images/p1.jpg' alt=''/><img src='p2.jpg'/></div>");
+        assert!(is_blank(&comic.text));
+        assert_eq!(comic.image.as_deref(), Some("../images/p1.jpg"));
+
+        let svg = page(
+            "<svg xmlns:xlink='http://www.w3.org/1999/xlink'><image xlink:href='p3.png'/></svg>",
+        );
+        assert_eq!(svg.image.as_deref(), Some("p3.png"));
+        assert_eq!(
+            resolve_path(Path::new("OEBPS/text/p1.xhtml"), "../images/p1.jpg"),
+            PathBuf::from("OEBPS/images/p1.jpg")
+        );
+    }
+
+    #[test]
+    fn merging_renumbers_link_markers_but_not_bracketed_text() {
+        let content = vec![
+            page("<p>See <a href='#a'>the notes</a> first.</p>"),
+            page("<p>As shown in [1], and <a href='#b'>[1]</a> here.</p>"),
+        ];
+        let (pages, _) = merge_empty_pages(content, 20);
+
+        let merged = &pages[0];
+        assert_eq!(
+            merged.text,
+            "See the notes[1] first.\n\nAs shown in [1], and [1][2] here."
+        );
+        for (number, link) in merged.links.iter().enumerate() {
+            let marker = format!("[{}]", number + 1);
+            assert_eq!(
+                &merged.text[link.marker..link.marker + marker.len()],
+                marker
+            );
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use crate::progress::ReadingPosition;
+use crate::{paths, Error};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// Loads the bookmarks of every book, keyed by book path
+pub fn load() -> HashMap<String, Vec<ReadingPosition>> {
+    paths::load_json(BOOKMARKS_FILE)
+}
+
+/// Saves the bookmarks of every book
+pub fn save(bookmarks: &HashMap<String, Vec<ReadingPosition>>) -> Result<(), Error> {
+    paths::save_json(BOOKMARKS_FILE, bookmarks)
+}
+
+/// Adds a bookmark keeping them in reading order, returns false if it already exists
+pub fn add(bookmarks: &mut Vec<ReadingPosition>, position: ReadingPosition) -> bool {
+    match bookmarks.binary_search_by_key(&(position.page, position.offset), |bookmark| {
+        (bookmark.page, bookmark.offset)
+    }) {
+        Ok(_) => false,
+        Err(index) => {
+            bookmarks.insert(index, position);
+            true
+        }
+    }
+}
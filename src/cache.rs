@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::time::SystemTime;
+
+use crate::{paths, Error, Page};
+
+/// Bumped whenever text extraction changes so older caches are rebuilt
+const CACHE_VERSION: u32 = 8;
+
+/// Extracted pages of a book along with what they were extracted from
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    version: u32,
+    path: String,
+    modified: SystemTime,
+    pages: Vec<Page>,
+}
+
+/// Cache file of a book, named after a hash of its path
+fn cache_file(path: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    format!("cache/{:016x}.json", hasher.finish())
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Loads the cached pages of a book, `None` if there are none or the book changed since
+pub fn load(path: &str) -> Option<Vec<Page>> {
+    let cached: Cached = serde_json::from_str(&paths::read(&cache_file(path))?).ok()?;
+    let valid = cached.version == CACHE_VERSION
+        && cached.path == path
+        && Some(cached.modified) == modified(path);
+    valid.then_some(cached.pages)
+}
+
+/// Caches the extracted pages of a book
+pub fn save(path: &str, pages: &[Page]) -> Result<(), Error> {
+    let Some(modified) = modified(path) else {
+        return Ok(());
+    };
+    let cached = Cached {
+        version: CACHE_VERSION,
+        path: path.to_string(),
+        modified,
+        pages: pages.to_vec(),
+    };
+    paths::write(&cache_file(path), serde_json::to_string(&cached).unwrap())
+}
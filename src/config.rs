@@ -0,0 +1,97 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::keys::KeyBindings;
+use crate::theme::Theme;
+use crate::{paths, Error};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Range the reading speed stays within
+pub const MIN_WPM: u16 = 50;
+pub const MAX_WPM: u16 = 1000;
+
+/// Range the reading width stays within, in columns
+pub const MIN_READING_WIDTH: u16 = 20;
+pub const MAX_READING_WIDTH: u16 = 400;
+
+/// User preferences that persist between sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub ruler_mode: RulerMode,
+    /// Maximum number of columns of text, wider terminals get margins
+    pub reading_width: u16,
+    pub theme: Theme,
+    /// Reading speed used for reading time estimates
+    pub words_per_minute: u16,
+    /// Scroll from the end of one page straight into the next
+    pub continuous: bool,
+    pub keys: KeyBindings,
+    /// The config file exists but could not be parsed, so saving leaves it untouched rather
+    /// than replacing the user's edits with defaults
+    #[serde(skip)]
+    pub keep_file: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            ruler_mode: RulerMode::default(),
+            reading_width: 80,
+            theme: Theme::default(),
+            words_per_minute: 238,
+            continuous: false,
+            keys: KeyBindings::default(),
+            keep_file: false,
+        }
+    }
+}
+
+/// How the reading ruler behaves while scrolling
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RulerMode {
+    /// The ruler stays on the same screen row and the text scrolls underneath it
+    #[default]
+    Fixed,
+    /// The ruler is pinned to a line of text and moves with it when scrolling
+    Follow,
+}
+
+impl RulerMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            RulerMode::Fixed => RulerMode::Follow,
+            RulerMode::Follow => RulerMode::Fixed,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file, falling back to defaults if it is missing
+    /// A reading speed or width outside of the supported range is clamped to it
+    pub fn load() -> Result<Self, Error> {
+        let mut config: Config = match paths::read(CONFIG_FILE) {
+            Some(data) => toml::from_str(&data).map_err(|error| Error::InvalidConfig {
+                file: paths::state_file(CONFIG_FILE).display().to_string(),
+                reason: error.message().to_string(),
+            })?,
+            None => Config::default(),
+        };
+        config.words_per_minute = config.words_per_minute.clamp(MIN_WPM, MAX_WPM);
+        config.reading_width = config
+            .reading_width
+            .clamp(MIN_READING_WIDTH, MAX_READING_WIDTH);
+        Ok(config)
+    }
+
+    /// Saves the config file, unless the one on disk couldn't be loaded
+    pub fn save(&self) -> Result<(), Error> {
+        if self.keep_file {
+            return Ok(());
+        }
+        let data = toml::to_string(self).unwrap();
+        paths::write(CONFIG_FILE, data)
+    }
+}
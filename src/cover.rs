@@ -0,0 +1,108 @@
+use image::{imageops::FilterType, RgbImage};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Color,
+    widgets::{Block, Borders, Widget},
+};
+use std::env;
+
+/// Largest side the decoded cover is kept at, plenty for a terminal
+const MAX_SIZE: u32 = 256;
+
+/// Characters from dark to light for terminals without 24-bit color
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// The cover image of a book, drawn with half-block characters
+#[derive(Debug)]
+pub struct Cover {
+    image: RgbImage,
+    true_color: bool,
+}
+
+impl Cover {
+    /// Decodes the cover image, returns None if the format isn't supported
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let image = image::load_from_memory(bytes).ok()?;
+        let image = image.thumbnail(MAX_SIZE, MAX_SIZE).to_rgb8();
+        // Terminals advertise 24-bit color through COLORTERM
+        let true_color =
+            env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit");
+        Some(Cover { image, true_color })
+    }
+
+    /// Scales the image to fit `width` by `height` pixels, keeping its aspect ratio
+    fn fit(&self, width: u32, height: u32) -> RgbImage {
+        let (image_width, image_height) = self.image.dimensions();
+        let scale = f64::min(
+            width as f64 / image_width as f64,
+            height as f64 / image_height as f64,
+        );
+        let width = ((image_width as f64 * scale) as u32).max(1);
+        let height = ((image_height as f64 * scale) as u32).max(1);
+        image::imageops::resize(&self.image, width, height, FilterType::Triangle)
+    }
+
+    /// Draws the image centered in `area` without a border, with half blocks if the terminal
+    /// has 24-bit color and ASCII art otherwise
+    pub fn render_image(&self, area: Rect, buf: &mut Buffer) {
+        if area.is_empty() {
+            return;
+        }
+        if self.true_color {
+            self.render_half_blocks(area, buf);
+        } else {
+            self.render_ascii(area, buf);
+        }
+    }
+
+    /// Each cell shows two pixels, the top one as foreground of '▀' and the bottom one as background
+    fn render_half_blocks(&self, area: Rect, buf: &mut Buffer) {
+        let image = self.fit(area.width as u32, area.height as u32 * 2);
+        let x = area.x + (area.width - image.width() as u16) / 2;
+        let y = area.y + (area.height - image.height().div_ceil(2) as u16) / 2;
+        for row in 0..image.height().div_ceil(2) {
+            for column in 0..image.width() {
+                let top = image.get_pixel(column, row * 2);
+                let cell = &mut buf[(x + column as u16, y + row as u16)];
+                cell.set_char('▀')
+                    .set_fg(Color::Rgb(top[0], top[1], top[2]));
+                if row * 2 + 1 < image.height() {
+                    let bottom = image.get_pixel(column, row * 2 + 1);
+                    cell.set_bg(Color::Rgb(bottom[0], bottom[1], bottom[2]));
+                }
+            }
+        }
+    }
+
+    /// Each cell shows one pixel as a character from the ASCII ramp
+    fn render_ascii(&self, area: Rect, buf: &mut Buffer) {
+        // Cells are about twice as tall as they are wide
+        let image = self.fit(area.width as u32, area.height as u32 * 2);
+        let image = image::imageops::resize(
+            &image,
+            image.width(),
+            image.height().div_ceil(2),
+            FilterType::Triangle,
+        );
+        let x = area.x + (area.width - image.width() as u16) / 2;
+        let y = area.y + (area.height - image.height() as u16) / 2;
+        for (column, row, pixel) in image.enumerate_pixels() {
+            let luma =
+                (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+            let index = luma as usize * (ASCII_RAMP.len() - 1) / 255;
+            buf[(x + column as u16, y + row as u16)].set_char(ASCII_RAMP[index] as char);
+        }
+    }
+}
+
+impl Widget for &Cover {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .title(" Cover (any key to continue) ")
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+        self.render_image(inner, buf);
+    }
+}
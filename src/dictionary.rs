@@ -0,0 +1,196 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::process::Command;
+
+use crate::{paths, Error};
+
+const DICTIONARY_FILE: &str = "dictionary.json";
+
+/// Seconds an online lookup may take before it is given up on
+const LOOKUP_TIMEOUT: &str = "5";
+
+/// Where definitions are looked up
+#[derive(Debug)]
+pub enum Source {
+    /// Definitions read from a JSON file, keyed by lowercase word
+    Local(HashMap<String, Vec<String>>),
+    /// A dictionary API, `{word}` in the URL is replaced by the word looked up
+    Http { url: String },
+}
+
+/// A definition in a dictionary file, either a single one or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Definitions {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Sources tried in order until one knows the word
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    sources: Vec<Source>,
+}
+
+impl Dictionary {
+    /// Uses the dictionary file at `file`, or `dictionary.json` in the config directory if it
+    /// exists, followed by the API at `url` if given
+    /// A missing or invalid file given explicitly is an error, the default one is optional
+    pub fn load(file: Option<&str>, url: Option<String>) -> Result<Self, Error> {
+        let mut sources = Vec::new();
+        let data = match file {
+            Some(file) => Some(
+                fs::read_to_string(file).map_err(|_| Error::InvalidDictionary {
+                    file: file.to_string(),
+                })?,
+            ),
+            None => paths::read(DICTIONARY_FILE),
+        };
+        if let Some(data) = data {
+            let file = file.map_or_else(
+                || paths::state_file(DICTIONARY_FILE).display().to_string(),
+                str::to_string,
+            );
+            sources.push(Source::Local(
+                parse(&data).ok_or(Error::InvalidDictionary { file })?,
+            ));
+        }
+        if let Some(url) = url {
+            sources.push(Source::Http { url });
+        }
+        Ok(Dictionary { sources })
+    }
+
+    /// Whether there is anywhere to look words up
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Definitions of `word` from the first source that has any, `None` if none do
+    pub fn lookup(&self, word: &str) -> Result<Option<Vec<String>>, Error> {
+        let word = normalize(word);
+        if word.is_empty() {
+            return Ok(None);
+        }
+        for source in &self.sources {
+            let definitions = match source {
+                Source::Local(words) => words.get(&word).cloned(),
+                Source::Http { url } => fetch(url, &word)?,
+            };
+            if definitions.is_some() {
+                return Ok(definitions);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Reads a dictionary file, a JSON object from each word to one definition or a list of them
+fn parse(data: &str) -> Option<HashMap<String, Vec<String>>> {
+    let entries: HashMap<String, Definitions> = serde_json::from_str(data).ok()?;
+    let words = entries
+        .into_iter()
+        .map(|(word, definitions)| {
+            let definitions = match definitions {
+                Definitions::One(definition) => vec![definition],
+                Definitions::Many(definitions) => definitions,
+            };
+            (normalize(&word), definitions)
+        })
+        .filter(|(_, definitions)| !definitions.is_empty())
+        .collect();
+    Some(words)
+}
+
+/// Lowercases a word and drops the punctuation around it, as in `"Hello,"`
+fn normalize(word: &str) -> String {
+    word.trim()
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// Asks a dictionary API for a word with `curl`, which has to be on the `PATH`
+/// A response with an error status means the word isn't known. JSON responses are searched
+/// for `definition` fields, anything else is shown as it is
+fn fetch(url: &str, word: &str) -> Result<Option<Vec<String>>, Error> {
+    let failed = |reason: String| Error::LookupFailed {
+        word: word.to_string(),
+        reason,
+    };
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--max-time",
+            LOOKUP_TIMEOUT,
+        ])
+        .arg(url.replace("{word}", &encode(word)))
+        .output()
+        .map_err(|error| {
+            failed(match error.kind() {
+                io::ErrorKind::NotFound => {
+                    "curl was not found, --dictionary-url needs it installed".to_string()
+                }
+                _ => format!("could not run curl: {}", error),
+            })
+        })?;
+    // curl exits with 22 when the server answers with an error status such as 404
+    match output.status.code() {
+        Some(0) => {}
+        Some(22) => return Ok(None),
+        _ => {
+            return Err(failed(
+                "the dictionary API could not be reached".to_string(),
+            ))
+        }
+    }
+    let body = String::from_utf8_lossy(&output.stdout);
+    let definitions = match serde_json::from_str::<Value>(&body) {
+        Ok(json) => {
+            let mut definitions = Vec::new();
+            collect_definitions(&json, &mut definitions);
+            definitions
+        }
+        Err(_) if body.trim().is_empty() => Vec::new(),
+        Err(_) => vec![body.trim().to_string()],
+    };
+    Ok((!definitions.is_empty()).then_some(definitions))
+}
+
+/// Gathers the text of every `definition` field in a JSON response
+fn collect_definitions(json: &Value, definitions: &mut Vec<String>) {
+    match json {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                match value {
+                    Value::String(definition) if name == "definition" => {
+                        definitions.push(definition.clone())
+                    }
+                    _ => collect_definitions(value, definitions),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_definitions(value, definitions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Percent-encodes a word for use in a URL
+fn encode(word: &str) -> String {
+    word.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::io;
+
+/// Errors returned by the reader's library functions
+#[derive(Debug)]
+pub enum Error {
+    /// The book file could not be read, e.g. it does not exist
+    OpenFailed { path: String, source: io::Error },
+    /// The book file was read but is not a valid EPUB
+    NotAnEpub { path: String },
+    /// The text of a page could not be extracted
+    ExtractFailed { path: String, page: usize },
+    /// Writing one of the state files in the config directory failed, such as the progress,
+    /// notes or config file
+    StateIo { file: String, source: io::Error },
+    /// Writing an export file failed
+    ExportFailed { file: String, source: io::Error },
+    /// A locator string could not be parsed
+    InvalidLocator { locator: String },
+    /// A dictionary file could not be read or is not a JSON object of definitions
+    InvalidDictionary { file: String },
+    /// The config file is not valid TOML or has a setting of the wrong type
+    InvalidConfig { file: String, reason: String },
+    /// Looking a word up in an online dictionary failed
+    LookupFailed { word: String, reason: String },
+    /// Drawing to or reading from the terminal failed
+    Terminal(io::Error),
+}
+
+impl Error {
+    /// Process exit code used when the error ends the program
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::OpenFailed { .. }
+            | Error::NotAnEpub { .. }
+            | Error::InvalidLocator { .. }
+            | Error::InvalidDictionary { .. }
+            | Error::InvalidConfig { .. } => 2,
+            Error::ExtractFailed { .. } => 3,
+            Error::StateIo { .. } | Error::ExportFailed { .. } | Error::LookupFailed { .. } => 4,
+            Error::Terminal(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OpenFailed { path, source } => write!(f, "Could not open {}: {}", path, source),
+            Error::NotAnEpub { path } => write!(f, "Could not open {}: not a valid EPUB", path),
+            Error::ExtractFailed { path, page } => {
+                write!(f, "Could not extract page {} of {}", page + 1, path)
+            }
+            Error::StateIo { file, source } => write!(f, "Could not save {}: {}", file, source),
+            Error::ExportFailed { file, source } => {
+                write!(f, "Could not write {}: {}", file, source)
+            }
+            Error::InvalidLocator { locator } => write!(
+                f,
+                "Invalid locator {}, expected epub://<identifier>/<chapter>@<line>",
+                locator
+            ),
+            Error::InvalidDictionary { file } => write!(
+                f,
+                "Could not read dictionary {}, expected a JSON object of words and definitions",
+                file
+            ),
+            Error::InvalidConfig { file, reason } => {
+                write!(f, "Could not read {}: {}", file, reason)
+            }
+            Error::LookupFailed { word, reason } => {
+                write!(f, "Could not look up {}: {}", word, reason)
+            }
+            Error::Terminal(source) => write!(f, "Terminal error: {}", source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::OpenFailed { source, .. }
+            | Error::StateIo { source, .. }
+            | Error::ExportFailed { source, .. }
+            | Error::Terminal(source) => Some(source),
+            Error::NotAnEpub { .. }
+            | Error::ExtractFailed { .. }
+            | Error::InvalidLocator { .. }
+            | Error::InvalidDictionary { .. }
+            | Error::InvalidConfig { .. }
+            | Error::LookupFailed { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Error::Terminal(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{book, Locator};
+    use std::fs;
+
+    #[test]
+    fn opening_a_missing_file_fails_to_open() {
+        let result = book::open("/nonexistent/book.epub");
+        assert!(matches!(result, Err(Error::OpenFailed { .. })));
+    }
+
+    #[test]
+    fn opening_a_file_that_is_not_a_zip_is_not_an_epub() {
+        let path = std::env::temp_dir().join("epub_reader_not_an_epub.epub");
+        fs::write(&path, "just some text").unwrap();
+        let result = book::open(&path.to_string_lossy());
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::NotAnEpub { .. })));
+    }
+
+    #[test]
+    fn parsing_a_malformed_locator_is_an_invalid_locator() {
+        for locator in [
+            "urn:isbn:123/5@120",
+            "epub://urn:isbn:123/0@1",
+            "epub://book/5",
+        ] {
+            let result = locator.parse::<Locator>();
+            assert!(
+                matches!(result, Err(Error::InvalidLocator { .. })),
+                "{}",
+                locator
+            );
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::book::{self, Page, TocEntry};
+use crate::Error;
+
+/// Joins the pages of a book into plain text, starting chapters with their title from the
+/// table of contents underlined with `=`
+pub fn to_text(pages: &[Page], toc: &[TocEntry]) -> String {
+    let mut text = String::new();
+    for (spine_index, page) in pages.iter().enumerate() {
+        let title = toc
+            .iter()
+            .find(|entry| entry.spine_index == Some(spine_index));
+        if let Some(entry) = title {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(&format!(
+                "{}\n{}\n\n",
+                entry.label,
+                "=".repeat(entry.label.chars().count())
+            ));
+        } else if book::is_blank(&page.text) {
+            continue;
+        } else if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(page.text.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Prints the metadata of the book at `path` to stdout as a JSON object with sorted fields
+pub fn print_metadata(path: &str) -> Result<(), Error> {
+    let epub = book::open(path)?;
+    let metadata: BTreeMap<String, Vec<String>> = book::metadata(&epub).into_iter().collect();
+    let json = serde_json::to_string_pretty(&metadata).unwrap();
+    writeln!(io::stdout(), "{}", json).map_err(|source| Error::ExportFailed {
+        file: "standard output".to_string(),
+        source,
+    })
+}
+
+/// Writes the text of every page of the book at `path` to a plain text file
+pub fn export(path: &str, file: &str) -> Result<(), Error> {
+    let mut epub = book::open(path)?;
+    let toc = book::toc_entries(&epub);
+    let pages = (0..epub.get_num_pages())
+        .map(|page| book::extract_page(&mut epub, path, page))
+        .collect::<Result<Vec<Page>, Error>>()?;
+    fs::write(file, to_text(&pages, &toc)).map_err(|source| Error::ExportFailed {
+        file: file.to_string(),
+        source,
+    })
+}
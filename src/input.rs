@@ -0,0 +1,103 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+/// What a prompt's text will be used for once submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    Note,
+    Search,
+    GoToPage,
+    Footnote,
+    Lookup,
+}
+
+impl Prompt {
+    fn title(self) -> &'static str {
+        match self {
+            Prompt::Note => " Note (Enter to save, Esc to cancel) ",
+            Prompt::Search => " Search (Enter to search, Esc to cancel) ",
+            Prompt::GoToPage => " Go to page (Enter to jump, Esc to cancel) ",
+            Prompt::Footnote => " Footnote number (Enter to show, Esc to cancel) ",
+            Prompt::Lookup => " Look up a word (Enter to look up, Esc to cancel) ",
+        }
+    }
+}
+
+/// Result of feeding a key press to an input line
+pub enum InputEvent {
+    Editing,
+    Submit,
+    Cancel,
+}
+
+/// A small single line text input shown at the bottom of the screen
+#[derive(Debug)]
+pub struct Input {
+    pub prompt: Prompt,
+    pub value: String,
+}
+
+impl Input {
+    pub fn new(prompt: Prompt) -> Self {
+        Input {
+            prompt,
+            value: String::new(),
+        }
+    }
+
+    /// An input that starts out holding `value`
+    pub fn with_value(prompt: Prompt, value: String) -> Self {
+        Input { prompt, value }
+    }
+
+    /// Applies a key press to the input
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> InputEvent {
+        match key_event.code {
+            KeyCode::Enter => InputEvent::Submit,
+            KeyCode::Esc => InputEvent::Cancel,
+            KeyCode::Backspace => {
+                self.value.pop();
+                InputEvent::Editing
+            }
+            KeyCode::Char(c) => {
+                self.value.push(c);
+                InputEvent::Editing
+            }
+            _ => InputEvent::Editing,
+        }
+    }
+}
+
+impl Input {
+    /// Where the input line is drawn, just above the bottom border of the reader
+    pub fn area(area: Rect) -> Rect {
+        let height = 3.min(area.height);
+        Rect::new(area.x, area.bottom() - height, area.width, height)
+    }
+}
+
+impl Widget for &Input {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let input_area = Input::area(area);
+        Clear.render(input_area, buf);
+
+        // Keep the end of long values visible
+        let width = input_area.width.saturating_sub(3) as usize;
+        let skip = self.value.chars().count().saturating_sub(width);
+        let visible: String = self.value.chars().skip(skip).collect();
+
+        Paragraph::new(Line::from(vec![visible.into(), "_".slow_blink()]))
+            .block(
+                Block::default()
+                    .title(self.prompt.title())
+                    .borders(Borders::ALL),
+            )
+            .render(input_area, buf);
+    }
+}
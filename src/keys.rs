@@ -0,0 +1,122 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Reader actions that can be bound to keys in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NextPage,
+    PreviousPage,
+    ScrollUp,
+    ScrollDown,
+    ScreenUp,
+    ScreenDown,
+    Quit,
+}
+
+/// Keys bound to each action, written as key names such as `"Right"` or `"l"`
+/// Actions missing from the config keep their default keys and unknown actions are ignored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(deserialize_with = "one_or_many")]
+    pub next_page: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub previous_page: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub scroll_up: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub scroll_down: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub screen_up: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub screen_down: Vec<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub quit: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            next_page: vec!["Right".into()],
+            previous_page: vec!["Left".into()],
+            scroll_up: vec!["Up".into()],
+            scroll_down: vec!["Down".into()],
+            screen_up: vec!["PageUp".into()],
+            screen_down: vec!["PageDown".into(), "Space".into()],
+            quit: vec!["q".into()],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Action bound to a key, if any
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        [
+            (&self.next_page, Action::NextPage),
+            (&self.previous_page, Action::PreviousPage),
+            (&self.scroll_up, Action::ScrollUp),
+            (&self.scroll_down, Action::ScrollDown),
+            (&self.screen_up, Action::ScreenUp),
+            (&self.screen_down, Action::ScreenDown),
+            (&self.quit, Action::Quit),
+        ]
+        .into_iter()
+        .find(|(keys, _)| keys.iter().any(|key| parse_key(key) == Some(code)))
+        .map(|(_, action)| action)
+    }
+
+    /// Label of the first key bound to an action, for on-screen hints
+    pub fn label(&self, action: Action) -> String {
+        let keys = match action {
+            Action::NextPage => &self.next_page,
+            Action::PreviousPage => &self.previous_page,
+            Action::ScrollUp => &self.scroll_up,
+            Action::ScrollDown => &self.scroll_down,
+            Action::ScreenUp => &self.screen_up,
+            Action::ScreenDown => &self.screen_down,
+            Action::Quit => &self.quit,
+        };
+        match keys.first() {
+            Some(key) => format!("<{}>", key),
+            None => "<unbound>".into(),
+        }
+    }
+}
+
+/// Parses a key name from the config file into a key code
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    match name.to_ascii_lowercase().as_str() {
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "pageup" => Some(KeyCode::PageUp),
+        "pagedown" => Some(KeyCode::PageDown),
+        "home" => Some(KeyCode::Home),
+        "end" => Some(KeyCode::End),
+        "enter" => Some(KeyCode::Enter),
+        "esc" => Some(KeyCode::Esc),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        "space" => Some(KeyCode::Char(' ')),
+        _ => None,
+    }
+}
+
+/// Accepts either a single key name or a list of them
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Keys {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Keys::deserialize(deserializer)? {
+        Keys::One(key) => vec![key],
+        Keys::Many(keys) => keys,
+    })
+}
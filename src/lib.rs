@@ -0,0 +1,26 @@
+//! Library behind the EPUB reader CLI: opening books, extracting their text,
+//! and the files used to remember progress, notes and settings between sessions.
+
+pub mod book;
+pub mod bookmarks;
+pub mod cache;
+pub mod config;
+pub mod dictionary;
+pub mod error;
+pub mod export;
+pub mod keys;
+pub mod library;
+pub mod locator;
+pub mod notes;
+pub mod paths;
+pub mod progress;
+pub mod queue;
+pub mod readability;
+pub mod settings;
+pub mod stats;
+pub mod text;
+pub mod theme;
+
+pub use book::{extract_text_from_xhtml, Book, EmptyPages, LineKind, Link, Page, TocEntry};
+pub use error::Error;
+pub use locator::Locator;
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{paths, Error};
+
+const LIBRARY_FILE: &str = "library.json";
+
+/// What the library picker shows about a book, remembered while it is read
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BookInfo {
+    pub title: String,
+    /// Share of the book read, from 0 to 100
+    pub percent: u8,
+    /// When the book was last read, in seconds since the Unix epoch
+    pub last_read: u64,
+}
+
+impl BookInfo {
+    pub fn new(title: String, percent: u8) -> Self {
+        let last_read = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        BookInfo {
+            title,
+            percent,
+            last_read,
+        }
+    }
+}
+
+/// Loads what is known about every book, keyed by book path
+pub fn load() -> HashMap<String, BookInfo> {
+    paths::load_json(LIBRARY_FILE)
+}
+
+/// Saves what is known about every book
+pub fn save(library: &HashMap<String, BookInfo>) -> Result<(), Error> {
+    paths::save_json(LIBRARY_FILE, library)
+}
+
+/// Paths of recently read books, most recent first
+/// Books with saved progress but no library entry, from older versions, come last
+pub fn recent<'a>(
+    books: impl IntoIterator<Item = &'a String>,
+    library: &HashMap<String, BookInfo>,
+) -> Vec<String> {
+    let mut books: Vec<String> = books.into_iter().cloned().collect();
+    books.sort_by(|a, b| {
+        let last_read = |path: &String| library.get(path).map_or(0, |info| info.last_read);
+        last_read(b).cmp(&last_read(a)).then_with(|| a.cmp(b))
+    });
+    books
+}
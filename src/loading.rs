@@ -0,0 +1,163 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Gauge, Paragraph, Widget},
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::cover::Cover;
+use epub_reader_cli::{book, cache, Book, Error, Page, TocEntry};
+
+/// Fewest rows worth showing the cover in on the splash screen
+const MIN_COVER_HEIGHT: u16 = 4;
+
+/// A book whose text has been extracted
+pub struct Loaded {
+    pub path: String,
+    pub metadata: HashMap<String, Vec<String>>,
+    pub rtl: bool,
+    pub toc: Vec<TocEntry>,
+    pub cover: Option<Cover>,
+    pub content: Vec<Page>,
+}
+
+/// Text extraction running in the background while a splash screen is shown
+#[derive(Debug)]
+pub struct Loading {
+    pub path: String,
+    pub metadata: HashMap<String, Vec<String>>,
+    pub rtl: bool,
+    pub toc: Vec<TocEntry>,
+    pub cover: Option<Cover>,
+    /// Color of the title and progress bar
+    color: Color,
+    total: usize,
+    done: Arc<AtomicUsize>,
+    handle: JoinHandle<Result<Vec<Page>, Error>>,
+}
+
+impl Loading {
+    /// Reads the book's metadata, table of contents and cover,
+    /// then starts extracting the text of every page in parallel unless it is cached
+    pub fn start(path: String, mut epub: Book, color: Color) -> Self {
+        let total = epub.get_num_pages();
+        let rtl = book::has_rtl_page_progression(&mut epub);
+        let toc = book::toc_entries(&epub);
+        let cover = epub
+            .get_cover()
+            .and_then(|(bytes, _mime)| Cover::decode(&bytes));
+        let metadata = book::metadata(&epub);
+        let done = Arc::new(AtomicUsize::new(0));
+        let handle = {
+            let path = path.clone();
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                if let Some(pages) = cache::load(&path) {
+                    return Ok(pages);
+                }
+                // Each worker thread opens the archive once and reuses it for all of its pages,
+                // rather than parsing the container again for every page
+                let books = rayon::broadcast(|_| book::open(&path).map(Mutex::new))
+                    .into_iter()
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let pages = (0..total)
+                    .into_par_iter()
+                    .map(|i| {
+                        // Only the worker a book was opened on locks it, so this never waits
+                        let worker = rayon::current_thread_index().unwrap_or_default();
+                        let mut epub = books[worker].lock().unwrap();
+                        let page = book::extract_page(&mut epub, &path, i)?;
+                        done.fetch_add(1, Ordering::Relaxed);
+                        Ok(page)
+                    })
+                    .collect::<Result<Vec<Page>, Error>>()?;
+                // A cache that can't be written only costs time on the next launch
+                let _ = cache::save(&path, &pages);
+                Ok(pages)
+            })
+        };
+        Loading {
+            path,
+            metadata,
+            rtl,
+            toc,
+            cover,
+            color,
+            total,
+            done,
+            handle,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the extraction and returns the book with the text of every page
+    pub fn finish(self) -> Result<Loaded, Error> {
+        Ok(Loaded {
+            content: self.handle.join().expect("text extraction panicked")?,
+            path: self.path,
+            metadata: self.metadata,
+            rtl: self.rtl,
+            toc: self.toc,
+            cover: self.cover,
+        })
+    }
+}
+
+impl Widget for &Loading {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let field = |name: &str| {
+            self.metadata
+                .get(name)
+                .and_then(|values| values.first())
+                .cloned()
+        };
+        let title = field("title").unwrap_or_else(|| self.path.clone());
+        let mut lines = vec![Line::from(title.bold().fg(self.color))];
+        if let Some(author) = field("creator") {
+            lines.push(Line::from(format!("by {}", author).italic()));
+        }
+
+        let block = Block::default().borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // Title and author sit in the middle of the screen with the progress bar below,
+        // or at the bottom beneath the cover if there is room for one
+        let text_height = lines.len() as u16;
+        let info_height = text_height + 2;
+        let top = match &self.cover {
+            Some(cover) if inner.height >= info_height + 1 + MIN_COVER_HEIGHT => {
+                let cover_height = inner.height - info_height - 1;
+                let cover_area = Rect::new(inner.x, inner.y, inner.width, cover_height);
+                cover.render_image(cover_area, buf);
+                inner.y + cover_height + 1
+            }
+            _ => inner.y + inner.height.saturating_sub(info_height) / 2,
+        };
+        let text_area = Rect::new(inner.x, top, inner.width, text_height.min(inner.height));
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .render(text_area, buf);
+
+        let gauge_y = top + text_height + 1;
+        if gauge_y < inner.bottom() {
+            let width = inner.width.min(50);
+            let gauge_area = Rect::new(inner.x + (inner.width - width) / 2, gauge_y, width, 1);
+            let done = self.done.load(Ordering::Relaxed);
+            Gauge::default()
+                .gauge_style(self.color)
+                .ratio(done as f64 / self.total.max(1) as f64)
+                .label(format!("Loading {}/{}", done, self.total))
+                .render(gauge_area, buf);
+        }
+    }
+}
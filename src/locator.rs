@@ -0,0 +1,48 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+
+const SCHEME: &str = "epub://";
+
+/// A shareable reading position, written as `epub://<identifier>/<chapter>@<line>`
+/// The chapter counts spine documents from 1 and the line is the scroll offset within it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locator {
+    pub identifier: String,
+    pub spine_index: usize,
+    pub offset: u16,
+}
+
+impl fmt::Display for Locator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}/{}@{}",
+            SCHEME,
+            self.identifier,
+            self.spine_index + 1,
+            self.offset
+        )
+    }
+}
+
+impl FromStr for Locator {
+    type Err = Error;
+
+    fn from_str(locator: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidLocator {
+            locator: locator.to_string(),
+        };
+        // Identifiers often contain slashes themselves, so the position is split off the end
+        let rest = locator.strip_prefix(SCHEME).ok_or_else(invalid)?;
+        let (identifier, position) = rest.rsplit_once('/').ok_or_else(invalid)?;
+        let (chapter, offset) = position.split_once('@').ok_or_else(invalid)?;
+        let chapter: usize = chapter.parse().map_err(|_| invalid())?;
+        Ok(Locator {
+            identifier: identifier.to_string(),
+            spine_index: chapter.checked_sub(1).ok_or_else(invalid)?,
+            offset: offset.parse().map_err(|_| invalid())?,
+        })
+    }
+}
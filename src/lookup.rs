@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use epub_reader_cli::dictionary::Dictionary;
+use epub_reader_cli::Error;
+
+/// A dictionary lookup running in the background, so a slow dictionary API doesn't freeze
+/// the reader while it answers
+#[derive(Debug)]
+pub struct Lookup {
+    pub word: String,
+    handle: JoinHandle<Result<Option<Vec<String>>, Error>>,
+}
+
+impl Lookup {
+    /// Starts looking `word` up in every source of the dictionary
+    pub fn start(dictionary: Arc<Dictionary>, word: String) -> Self {
+        let handle = {
+            let word = word.clone();
+            thread::spawn(move || dictionary.lookup(&word))
+        };
+        Lookup { word, handle }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the lookup and returns the definitions found, if any
+    pub fn finish(self) -> Result<Option<Vec<String>>, Error> {
+        self.handle.join().expect("dictionary lookup panicked")
+    }
+}
@@ -1,151 +1,565 @@
 use clap::Parser;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use epub::doc::EpubDoc;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseEventKind,
+};
+use crossterm::execute;
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Rect},
-    style::Stylize,
+    style::{Color, Style, Stylize},
     symbols::border,
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::{
         block::{Position, Title},
-        Block, Borders, Clear, Paragraph, Widget, Wrap,
+        Block, Borders, Clear, List, ListState, Paragraph, StatefulWidget, Widget, Wrap,
     },
     DefaultTerminal, Frame,
 };
-use rayon::prelude::*;
-use scraper::{Html, Selector};
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::fs;
-use std::io;
+use std::io::stdout;
+use std::panic;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod cover;
+mod input;
+mod loading;
+mod lookup;
+
+use cover::Cover;
+use epub_reader_cli::book::{self, Book, EmptyPages, LineKind, Page};
+use epub_reader_cli::bookmarks;
+use epub_reader_cli::config::{
+    Config, RulerMode, MAX_READING_WIDTH, MAX_WPM, MIN_READING_WIDTH, MIN_WPM,
+};
+use epub_reader_cli::dictionary::Dictionary;
+use epub_reader_cli::export;
+use epub_reader_cli::keys::Action;
+use epub_reader_cli::library::{self, BookInfo};
+use epub_reader_cli::notes::{self, Annotation};
+use epub_reader_cli::progress::{self, ReadingPosition};
+use epub_reader_cli::settings::{self, BookSettings};
+use epub_reader_cli::stats::{self, BookStats};
+use epub_reader_cli::theme::{Palette, Theme};
+use epub_reader_cli::{queue, readability, text, Error, Link, Locator, TocEntry};
+use input::{Input, InputEvent, Prompt};
+use loading::{Loaded, Loading};
+use lookup::Lookup;
 
 /// CLI arguments using Clap for command-line parsing
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Args {
-    /// Path of the epub file
+    /// Path of the epub file, leave out to pick from recently read books
     #[arg(short, long)]
-    path: String,
+    path: Option<String>,
+
+    /// words per minute used to calculate estimated reading time, for this session only
+    /// 238 is the Adult Average Reading Speed so is the default
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(MIN_WPM as i64..=MAX_WPM as i64))]
+    words_per_minute: Option<u16>,
+
+    /// How to treat spine documents that are empty or whitespace-only after extraction
+    #[arg(long, value_enum, default_value_t = EmptyPages::Keep)]
+    empty_pages: EmptyPages,
+
+    /// With --empty-pages skip or merge, also treat pages with fewer words than this as empty.
+    /// Pages holding only a table of contents always count as empty
+    #[arg(long, value_name = "WORDS", default_value_t = 1)]
+    min_words: usize,
+
+    /// Reading ruler behaviour, saved to the config file when given
+    #[arg(long, value_enum)]
+    ruler_mode: Option<RulerMode>,
+
+    /// Scroll from the end of one page straight into the next, saved to the config file when given
+    #[arg(long)]
+    continuous: bool,
+
+    /// Write the text of the whole book to a plain text file and exit
+    #[arg(long, value_name = "FILE", requires = "path")]
+    export: Option<String>,
+
+    /// Print the metadata of the book as JSON and exit
+    #[arg(long, requires = "path")]
+    print_metadata: bool,
+
+    /// Color theme, for this session only
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
+
+    /// Write the notes for the book to a markdown file and exit
+    #[arg(long, value_name = "FILE", requires = "path")]
+    export_notes: Option<String>,
+
+    /// Read right-to-left: Left turns to the next page and Right to the previous one.
+    /// Enabled automatically for books whose spine declares a right-to-left page progression
+    #[arg(long)]
+    manga: bool,
+
+    /// Add the book to the end of the reading queue and exit
+    #[arg(long, requires = "path")]
+    add_to_queue: bool,
 
-    /// words per minute used to calculate estimated reading time
-    /// 238 is the Adult Average Reading Speed so is a sensible default
-    #[arg(short, long, default_value_t = 238)]
-    words_per_minute: u16,
+    /// Show typographic quotes, dashes and ellipses as plain ASCII.
+    /// Only the display changes, notes keep the original text
+    #[arg(long)]
+    ascii_punctuation: bool,
+
+    /// Open at a position shared with the <G> key, e.g. epub://urn:isbn:123/5@120
+    #[arg(long, value_name = "LOCATOR")]
+    goto_locator: Option<Locator>,
+
+    /// JSON file of words and their definitions for the <d> key,
+    /// dictionary.json in the config directory is used when left out
+    #[arg(long, value_name = "FILE")]
+    dictionary: Option<String>,
+
+    /// Dictionary API to ask for words the dictionary file doesn't have, {word} is replaced
+    /// by the word, e.g. https://api.dictionaryapi.dev/api/v2/entries/en/{word}. Needs curl
+    #[arg(long, value_name = "URL")]
+    dictionary_url: Option<String>,
 }
 
 /// Main application state structure
 #[derive(Debug, Default)]
 pub struct App {
-    content: Vec<String>,
+    content: Vec<Page>,
     text: String,
     page: u16,
     pages: u16,
     path: String,
     wpm: u16,
+    reading_width: u16,
+    theme: Theme,
+    /// Settings last used for each book, keyed by book path
+    book_settings: HashMap<String, BookSettings>,
+    /// Settings given on the command line, used for every book this session until changed
+    /// from the keyboard
+    overrides: BookSettings,
     exit: bool,
     scroll_offset: u16,
-    progress: HashMap<String, u16>,
+    progress: HashMap<String, ReadingPosition>,
     popup_text: Option<String>,
+    popup_title: &'static str,
     show_metadata: Option<String>,
+    /// Marker number and text of the footnote shown over the page
+    footnote: Option<(usize, String)>,
+    dictionary: Arc<Dictionary>,
+    /// Lookup still waiting on the dictionary
+    lookup: Option<Lookup>,
+    /// Word and definitions shown over the page
+    definition: Option<(String, String)>,
     metadata: HashMap<String, Vec<String>>,
+    readability: Vec<Option<f32>>,
+    empty_pages: EmptyPages,
+    min_words: usize,
+    spine_pages: Vec<usize>,
+    config: Config,
+    ruler: bool,
+    ruler_line: u16,
+    selection: Option<(u16, u16)>,
+    input: Option<Input>,
+    notes: HashMap<String, Vec<Annotation>>,
+    bookmarks: HashMap<String, Vec<ReadingPosition>>,
+    show_bookmarks: bool,
+    show_links: bool,
+    show_help: bool,
+    stats: HashMap<String, BookStats>,
+    /// When the reader last pressed a key, reading time is counted between presses
+    last_activity: Option<Instant>,
+    link_index: usize,
+    cover: Option<Cover>,
+    show_cover: bool,
+    /// Page index and decoded image of the current page when it has no text of its own,
+    /// `None` for the image if it couldn't be read
+    page_image: Option<(u16, Option<Cover>)>,
+    bookmark_index: usize,
+    view_height: Cell<u16>,
+    /// Width of the text column in the last render, lines are wrapped to it
+    view_width: Cell<u16>,
+    manga: bool,
+    force_manga: bool,
+    queue: Vec<String>,
+    show_queue: bool,
+    queue_index: usize,
+    finished: bool,
+    library: HashMap<String, BookInfo>,
+    /// Recently read books shown in the library picker, most recent first
+    recent_books: Vec<String>,
+    show_library: bool,
+    library_index: usize,
+    ascii_punctuation: bool,
+    loading: Option<Loading>,
+    goto: Option<Locator>,
+    toc: Vec<TocEntry>,
+    show_toc: bool,
+    toc_index: usize,
+    search_query: Option<String>,
+    search_hits: Vec<(usize, usize)>,
+    search_index: usize,
+    line_counts: Vec<usize>,
+    /// When auto-scroll moves on to the next line, `None` while it is paused
+    auto_scroll: Option<Instant>,
+}
+
+/// Highlight used for the reading ruler
+const RULER_STYLE: Style = Style::new().bg(Color::DarkGray);
+
+/// Highlight used for search matches
+const MATCH_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
+/// Highlight used for selected lines
+const SELECTION_STYLE: Style = Style::new().bg(Color::Blue);
+
+/// How the text of a page is wrapped, both when drawn and when counting its rows for scrolling
+/// Leading whitespace is kept so nested list items and preformatted text stay indented
+const TEXT_WRAP: Wrap = Wrap { trim: false };
+
+/// Smallest terminal size the reader will attempt to lay out
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 5;
+
+/// Time without a key press after which reading time stops counting
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Words per minute the reading speed changes by with `+` and `-`
+const WPM_STEP: u16 = 10;
+
+/// Shortest time auto-scroll stays on a line, so blank lines and headings still go by readably
+const MIN_AUTO_SCROLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a pending dictionary lookup is checked on while waiting for input
+const LOOKUP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lines scrolled per mouse wheel tick
+const MOUSE_SCROLL_LINES: u16 = 3;
+
+/// Columns the reading width changes by with `[` and `]`
+const READING_WIDTH_STEP: i32 = 5;
+
+/// Formats a duration in seconds as "45s", "12m" or "2h 13m"
+fn format_duration(seconds: u32) -> String {
+    let minutes = seconds.div_ceil(60);
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    }
 }
 
 /// Helper function to center a popup in the terminal
 /// Calculates the centered rectangle based on percentage of screen width and height
+/// Percentages above 100 are treated as 100 so the popup never exceeds the area
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_width = r.width * percent_x / 100;
-    let popup_height = r.height * percent_y / 100;
-    let popup_x = (r.width - popup_width) / 2;
-    let popup_y = (r.height - popup_height) / 2;
+    let popup_width = (r.width as u32 * percent_x.min(100) as u32 / 100) as u16;
+    let popup_height = (r.height as u32 * percent_y.min(100) as u32 / 100) as u16;
+    let popup_x = r.width.saturating_sub(popup_width) / 2;
+    let popup_y = r.height.saturating_sub(popup_height) / 2;
     Rect {
-        x: popup_x,
-        y: popup_y,
+        x: r.x + popup_x,
+        y: r.y + popup_y,
         width: popup_width,
         height: popup_height,
     }
 }
 
-pub fn extract_text_from_xhtml(xhtml: &str) -> String {
-    let document = Html::parse_document(xhtml);
-
-    // Select the body of the HTML document
-    let selector = Selector::parse("body").unwrap();
-    let mut text = String::new();
-
-    for element in document.select(&selector) {
-        // Instead of joining with spaces, we join with newlines to preserve formatting
-        text.push_str(&element.text().collect::<Vec<_>>().join("\n"));
-    }
-
-    text
-}
-
 impl App {
     /// Runs the application's main loop until the user quits
-    /// Loads the epub file, processes the pages, and manages user input
+    /// Processes the pages of the already opened epub file, or starts in the library picker
+    /// without one, and manages user input
     pub fn run(
         &mut self,
         terminal: &mut DefaultTerminal,
+        epub: Option<Book>,
         Args {
             path,
             words_per_minute,
+            empty_pages,
+            min_words,
+            ruler_mode,
+            continuous,
+            theme,
+            manga,
+            ascii_punctuation,
+            goto_locator,
+            ..
         }: Args,
-    ) -> io::Result<()> {
+    ) -> Result<(), Error> {
         self.load_progress();
-        let num_pages = {
-            let epub = EpubDoc::new(&path).unwrap();
-            epub.get_num_pages()
+        self.notes = notes::load();
+        self.bookmarks = bookmarks::load();
+        self.stats = stats::load();
+        self.queue = queue::load();
+        self.library = library::load();
+        self.book_settings = settings::load();
+        self.config = match Config::load() {
+            Ok(config) => config,
+            Err(error) => {
+                self.popup_title = "Error";
+                self.popup_text = Some(format!(
+                    "{}\nThe defaults are used and the file is left unchanged.\n\n\n \
+                     Press <C> to close pop-up!",
+                    error
+                ));
+                Config {
+                    keep_file: true,
+                    ..Config::default()
+                }
+            }
         };
-
-        let metadata = {
-            let epub = EpubDoc::new(&path).unwrap();
-            epub.metadata
+        if let Some(ruler_mode) = ruler_mode {
+            self.config.ruler_mode = ruler_mode;
+            self.config.save()?;
+        }
+        if continuous {
+            self.config.continuous = true;
+            self.config.save()?;
+        }
+        // The reading speed and theme given on the command line only last for this session
+        self.overrides = BookSettings {
+            words_per_minute,
+            reading_width: None,
+            theme,
         };
-
-        // Process pages in parallel
-        let content: Vec<String> = (0..num_pages)
-            .into_par_iter()
-            .map(|i| {
-                // Open a new instance of EpubDoc for each thread
-                let mut epub = EpubDoc::new(&path).unwrap();
-                epub.set_current_page(i);
-                extract_text_from_xhtml(&epub.get_current_str().unwrap().0)
-            })
-            .collect();
-
-        self.content = content;
-        self.pages = num_pages as u16;
-        self.page = *self.progress.get(&path).unwrap_or(&0);
-        self.text = self.content[self.page as usize].clone();
-        self.metadata = metadata;
+        self.apply_book_settings(path.as_deref().unwrap_or_default());
+        self.empty_pages = empty_pages;
+        self.min_words = min_words;
+        self.force_manga = manga;
+        self.ascii_punctuation = ascii_punctuation;
+        self.goto = goto_locator;
+        match (path, epub) {
+            (Some(path), Some(epub)) => self.load_book(path, epub),
+            _ => self.open_library(),
+        }
 
         while !self.exit {
-            self.path = path.clone();
-            self.wpm = words_per_minute;
+            if self.loading.as_ref().is_some_and(Loading::is_finished) {
+                self.finish_loading()?;
+            }
+            if self.lookup.as_ref().is_some_and(Lookup::is_finished) {
+                self.finish_lookup();
+            }
+            self.update_page_image();
             terminal.draw(|frame| self.draw(frame))?;
-            self.progress.insert(self.path.clone(), self.page);
+            if self.loading.is_some() {
+                self.handle_loading_events()?;
+                continue;
+            }
             self.save_progress();
             self.handle_events()?;
         }
+        self.update_library();
+        self.save_stats();
+        Ok(())
+    }
+
+    /// Decodes the image of the current page if it has no text, comic and manga pages are
+    /// usually a single image. Only the image of the page being shown is kept
+    fn update_page_image(&mut self) {
+        let image = self
+            .content
+            .get(self.page as usize)
+            .filter(|page| book::is_blank(&page.text))
+            .and_then(|page| page.image.clone());
+        let Some(image) = image else {
+            self.page_image = None;
+            return;
+        };
+        if self
+            .page_image
+            .as_ref()
+            .is_some_and(|(page, _)| *page == self.page)
+        {
+            return;
+        }
+        let cover = book::open(&self.path)
+            .ok()
+            .and_then(|mut epub| epub.get_resource_by_path(&image))
+            .and_then(|bytes| Cover::decode(&bytes));
+        self.page_image = Some((self.page, cover));
+    }
+
+    /// Processes the pages of the epub file opened from `path` and restores the saved position
+    fn load_book(&mut self, path: String, epub: Book) {
+        // The book being closed keeps the progress it was left at in the picker
+        self.update_library();
+        self.apply_book_settings(&path);
+        // Pages are processed in parallel in the background while the splash screen shows progress
+        self.loading = Some(Loading::start(path, epub, self.palette().text));
+    }
+
+    /// Installs the text of a book once its background extraction is done
+    fn finish_loading(&mut self) -> Result<(), Error> {
+        let Some(loading) = self.loading.take() else {
+            return Ok(());
+        };
+        let Loaded {
+            path,
+            metadata,
+            rtl,
+            toc,
+            cover,
+            content,
+        } = loading.finish()?;
+        let num_pages = content.len();
+
+        let (content, spine_pages) = match self.empty_pages {
+            EmptyPages::Merge => book::merge_empty_pages(content, self.min_words),
+            EmptyPages::Keep | EmptyPages::Skip => (content, (0..num_pages).collect()),
+        };
+
+        let language = metadata.get("language").and_then(|values| values.first());
+        self.readability = readability::score_pages(&content, language.map(String::as_str));
+        self.pages = content.len() as u16;
+        self.line_counts = content
+            .iter()
+            .map(|page| page.text.lines().count())
+            .collect();
+        self.content = content;
+        self.spine_pages = spine_pages;
+        // Matches from the previous book would point at lines that no longer exist
+        self.search_query = None;
+        self.search_hits.clear();
+        self.search_index = 0;
+        // Books opened for the first time start on their cover
+        self.show_cover = cover.is_some() && !self.progress.contains_key(&path);
+        self.cover = cover;
+        self.page_image = None;
+        let saved = self.progress.get(&path).cloned().unwrap_or_default();
+        self.go_to_position(&saved);
+        self.metadata = metadata;
+        self.manga = self.force_manga || rtl;
+        self.toc = toc;
+        self.path = path;
+        self.last_activity = Some(Instant::now());
+        self.update_library();
+        if let Some(locator) = self.goto.take() {
+            self.go_to_locator(locator);
+        }
         Ok(())
     }
 
+    /// Uses the settings last used for the book at `path`, or the defaults from the config file
+    /// for settings it doesn't have, options given on the command line take precedence
+    fn apply_book_settings(&mut self, path: &str) {
+        let saved = self.book_settings.get(path).copied().unwrap_or_default();
+        self.wpm = self
+            .overrides
+            .words_per_minute
+            .or(saved.words_per_minute)
+            .unwrap_or(self.config.words_per_minute)
+            .clamp(MIN_WPM, MAX_WPM);
+        self.reading_width = self
+            .overrides
+            .reading_width
+            .or(saved.reading_width)
+            .unwrap_or(self.config.reading_width)
+            .clamp(MIN_READING_WIDTH, MAX_READING_WIDTH);
+        self.theme = self
+            .overrides
+            .theme
+            .or(saved.theme)
+            .unwrap_or(self.config.theme);
+    }
+
+    /// Changes the settings remembered for the open book and saves them
+    fn update_book_settings(&mut self, update: impl FnOnce(&mut BookSettings)) {
+        if self.path.is_empty() {
+            return;
+        }
+        update(self.book_settings.entry(self.path.clone()).or_default());
+        let saved = settings::save(&self.book_settings);
+        self.report(saved);
+    }
+
+    /// Colors of the current theme
+    fn palette(&self) -> Palette {
+        self.theme.palette()
+    }
+
+    /// Clears the area behind a popup and paints it in the theme's popup colors
+    fn clear_popup(&self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(Clear, area);
+        frame.buffer_mut().set_style(area, self.palette().popup);
+    }
+
     /// Draws the UI for the current application state
     fn draw(&self, frame: &mut Frame) {
-        frame.render_widget(self, frame.area());
+        // Layout breaks down on tiny terminals, ask for more room until the next resize
+        let area = frame.area();
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            let message = Paragraph::new("Terminal too small, please resize")
+                .wrap(Wrap { trim: true })
+                .alignment(Alignment::Center);
+            frame.render_widget(message, area);
+            return;
+        }
+
+        frame.buffer_mut().set_style(area, self.palette().base);
+
+        if let Some(ref loading) = self.loading {
+            frame.render_widget(loading, area);
+            return;
+        }
+
+        if let Some(cover) = self.cover.as_ref().filter(|_| self.show_cover) {
+            frame.render_widget(cover, area);
+            return;
+        }
+
+        // Without a book only the library picker is shown
+        if !self.path.is_empty() {
+            frame.render_widget(self, frame.area());
+        }
+
+        if self.show_library {
+            self.render_library(frame);
+        }
+
+        if self.show_queue {
+            self.render_queue(frame);
+        }
+
+        if self.show_toc {
+            self.render_toc(frame);
+        }
+
+        if self.show_bookmarks {
+            self.render_bookmarks(frame);
+        }
+
+        if self.show_links {
+            self.render_links(frame);
+        }
+
+        if self.show_help {
+            self.render_help(frame);
+        }
 
         // If there's a popup to show, render it
         if let Some(ref popup_text) = self.popup_text {
             let popup_area = centered_rect(60, 20, frame.area()); // Center the popup
-            frame.render_widget(Clear, popup_area); // Clear the background behind the popup
+            self.clear_popup(frame, popup_area); // Clear the background behind the popup
             let popup = Paragraph::new(popup_text.clone()) // Use popup_text here
-            .block(Block::default().title("Reading Time").borders(Borders::ALL));
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .title(self.popup_title)
+                        .borders(Borders::ALL),
+                );
             frame.render_widget(popup, popup_area);
         }
 
         if let Some(ref show_metadata) = self.show_metadata {
             let popup_area = centered_rect(70, 60, frame.area());
-            frame.render_widget(Clear, popup_area);
+            self.clear_popup(frame, popup_area);
 
             // Render the metadata popup
             let popup = Paragraph::new(show_metadata.clone()).block(
@@ -155,34 +569,262 @@ impl App {
             );
             frame.render_widget(popup, popup_area);
         }
+
+        if let Some((number, ref note)) = self.footnote {
+            let popup_area = centered_rect(60, 40, frame.area());
+            self.clear_popup(frame, popup_area);
+            let popup = Paragraph::new(note.clone())
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .title(format!(" Footnote [{}] ", number))
+                        .title(
+                            Title::from(" <c> close ")
+                                .alignment(Alignment::Center)
+                                .position(Position::Bottom),
+                        )
+                        .borders(Borders::ALL),
+                );
+            frame.render_widget(popup, popup_area);
+        }
+
+        if let Some((ref word, ref definition)) = self.definition {
+            let popup_area = centered_rect(60, 40, frame.area());
+            self.clear_popup(frame, popup_area);
+            let popup = Paragraph::new(definition.clone())
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .title(format!(" {} ", word))
+                        .title(
+                            Title::from(" <c> close ")
+                                .alignment(Alignment::Center)
+                                .position(Position::Bottom),
+                        )
+                        .borders(Borders::ALL),
+                );
+            frame.render_widget(popup, popup_area);
+        }
+
+        if let Some(ref input) = self.input {
+            frame.render_widget(input, frame.area());
+            let input_area = Input::area(frame.area());
+            frame
+                .buffer_mut()
+                .set_style(input_area, self.palette().popup);
+        }
     }
 
     /// updates the application's state based on user input
-    fn handle_events(&mut self) -> io::Result<()> {
-        match event::read()? {
+    /// While auto-scrolling, waits for input only until the next line is due
+    fn handle_events(&mut self) -> Result<(), Error> {
+        // Wake up for the next auto-scroll step and to show a finished lookup
+        let due = self.auto_scroll;
+        let mut timeout = due.map(|due| due.saturating_duration_since(Instant::now()));
+        if self.lookup.is_some() {
+            timeout = Some(timeout.map_or(LOOKUP_POLL_INTERVAL, |timeout| {
+                timeout.min(LOOKUP_POLL_INTERVAL)
+            }));
+        }
+        if let Some(timeout) = timeout {
+            if !event::poll(timeout)? {
+                if due.is_some_and(|due| due <= Instant::now()) {
+                    self.record_reading_time();
+                    self.auto_scroll_tick();
+                }
+                return Ok(());
+            }
+        }
+        let event = event::read()?;
+        self.record_reading_time();
+        match event {
             // it's important to check that the event is a key press event as
             // crossterm also emits key release and repeat events on Windows.
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event)
+                // Any key pauses auto-scroll, <a> only pauses it
+                let paused = self.auto_scroll.take().is_some();
+                if !(paused && key_event.code == KeyCode::Char('a')) {
+                    self.handle_key_event(key_event)
+                }
             }
+            Event::Resize(width, height) => self.handle_resize(width, height),
+            Event::Mouse(mouse_event) => match mouse_event.kind {
+                MouseEventKind::ScrollUp => self.handle_mouse_scroll(false),
+                MouseEventKind::ScrollDown => self.handle_mouse_scroll(true),
+                _ => {}
+            },
             _ => {}
         };
         Ok(())
     }
 
+    /// Scrolls whatever is in front with the mouse wheel
+    /// Open lists move their selection as with the arrow keys, screens without one ignore the
+    /// wheel, and the text scrolls a few lines at a time, pausing auto-scroll like a key would
+    fn handle_mouse_scroll(&mut self, down: bool) {
+        if self.input.is_some() || self.show_cover || self.show_help {
+            return;
+        }
+        if self.show_library
+            || self.show_queue
+            || self.show_toc
+            || self.show_bookmarks
+            || self.show_links
+        {
+            let code = if down { KeyCode::Down } else { KeyCode::Up };
+            self.handle_key_event(KeyEvent::from(code));
+            return;
+        }
+        self.auto_scroll = None;
+        for _ in 0..MOUSE_SCROLL_LINES {
+            if down {
+                self.scroll_down();
+            } else {
+                self.scroll_up();
+            }
+        }
+    }
+
+    /// Keeps the top visible line in place when the terminal is resized,
+    /// only scrolling back if the taller view would run past the end of the page
+    fn handle_resize(&mut self, width: u16, height: u16) {
+        self.view_height.set(height.saturating_sub(2));
+        self.view_width
+            .set(width.saturating_sub(2).min(self.reading_width));
+        self.scroll_offset = self.scroll_offset.min(self.max_scroll_offset());
+    }
+
+    /// Only quitting is possible while a book loads, the splash keeps redrawing in between
+    fn handle_loading_events(&mut self) -> Result<(), Error> {
+        if event::poll(Duration::from_millis(50))? {
+            if let Event::Key(key_event) = event::read()? {
+                if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Char('q') {
+                    self.exit();
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        // An open input line captures all keys until it is submitted or cancelled
+        if let Some(ref mut input) = self.input {
+            match input.handle_key_event(key_event) {
+                InputEvent::Submit => {
+                    if let Some(input) = self.input.take() {
+                        self.submit_input(input);
+                    }
+                }
+                InputEvent::Cancel => self.input = None,
+                InputEvent::Editing => {}
+            }
+            return;
+        }
+
+        if self.show_library {
+            self.handle_library_key_event(key_event);
+            return;
+        }
+
+        // Any key moves on from the cover
+        if self.show_cover {
+            self.show_cover = false;
+            return;
+        }
+
+        if self.show_queue {
+            self.handle_queue_key_event(key_event);
+            return;
+        }
+
+        if self.show_toc {
+            self.handle_toc_key_event(key_event);
+            return;
+        }
+
+        if self.show_bookmarks {
+            self.handle_bookmarks_key_event(key_event);
+            return;
+        }
+
+        if self.show_links {
+            self.handle_links_key_event(key_event);
+            return;
+        }
+
+        if self.show_help {
+            if matches!(
+                key_event.code,
+                KeyCode::Char('c') | KeyCode::Esc | KeyCode::Char('?')
+            ) {
+                self.show_help = false;
+            }
+            return;
+        }
+
+        if let Some(action) = self.config.keys.action(key_event.code) {
+            match action {
+                Action::Quit => self.exit(),
+                Action::ScrollUp if self.selection.is_some() => self.extend_selection_up(),
+                Action::ScrollDown if self.selection.is_some() => self.extend_selection_down(),
+                Action::NextPage if self.manga => self.previous_page(),
+                Action::PreviousPage if self.manga => self.next_page(),
+                Action::NextPage => self.next_page(),
+                Action::PreviousPage => self.previous_page(),
+                Action::ScrollUp => self.scroll_up(),
+                Action::ScrollDown => self.scroll_down(),
+                Action::ScreenUp => self.screen_up(),
+                Action::ScreenDown => self.screen_down(),
+            }
+            return;
+        }
+
         match key_event.code {
-            KeyCode::Char('q') => self.exit(),
-            KeyCode::Left => self.previous_page(),
-            KeyCode::Right => self.next_page(),
-            KeyCode::Up => self.scroll_up(),
-            KeyCode::Down => self.scroll_down(),
+            KeyCode::Esc if self.selection.is_some() => self.selection = None,
+            KeyCode::Char('n') if self.selection.is_some() => {
+                self.input = Some(Input::new(Prompt::Note))
+            }
             KeyCode::Char('s') => self.show_reading_time(),
-            KeyCode::Char('c') => {
+            KeyCode::Char('c') | KeyCode::Esc => {
                 self.popup_text = None;
                 self.show_metadata = None;
+                self.footnote = None;
+                self.definition = None;
+                self.lookup = None;
             }
             KeyCode::Char('m') => self.show_metadata(),
+            KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Char('r') => self.toggle_ruler(),
+            KeyCode::Char('R') => self.toggle_ruler_mode(),
+            KeyCode::Char('p') => self.toggle_theme(),
+            KeyCode::Char('C') => self.toggle_continuous(),
+            KeyCode::Char('a') => self.schedule_auto_scroll(),
+            KeyCode::Char('+') => self.change_wpm(WPM_STEP as i32),
+            KeyCode::Char('-') => self.change_wpm(-(WPM_STEP as i32)),
+            KeyCode::Char('[') => self.change_reading_width(-READING_WIDTH_STEP),
+            KeyCode::Char(']') => self.change_reading_width(READING_WIDTH_STEP),
+            KeyCode::Char('v') => self.toggle_selection(),
+            KeyCode::Char('u') => self.open_queue(false),
+            KeyCode::Char('o') => self.open_library(),
+            KeyCode::Char('b') => self.add_bookmark(),
+            KeyCode::Char('i') => self.show_cover(),
+            KeyCode::Char('f') => {
+                self.link_index = 0;
+                self.show_links = true;
+            }
+            KeyCode::Char('B') => self.open_bookmarks(),
+            KeyCode::Char('G') => self.show_locator(),
+            KeyCode::Char('t') => self.open_toc(),
+            KeyCode::Char('/') => self.input = Some(Input::new(Prompt::Search)),
+            KeyCode::Char('g') => self.input = Some(Input::new(Prompt::GoToPage)),
+            KeyCode::Char('F') => self.input = Some(Input::new(Prompt::Footnote)),
+            // The word searched for is offered first, it's likely the one to look up
+            KeyCode::Char('d') => {
+                let word = self.search_query.clone().unwrap_or_default();
+                self.input = Some(Input::with_value(Prompt::Lookup, word))
+            }
+            KeyCode::Char('n') => self.next_match(),
+            KeyCode::Char('N') => self.previous_match(),
             _ => {}
         }
     }
@@ -192,138 +834,1872 @@ impl App {
     }
 
     /// This will handle going to next page, if not already at the last page
+    /// Turning past the last page offers the next book in the reading queue
     fn next_page(&mut self) {
-        if self.page != self.pages - 1 {
-            self.page += 1;
-            self.text = self.content[self.page as usize].clone();
+        let next = self.following_pages().next();
+        if let Some(page) = next {
+            self.page = page;
+            self.text = self.content[self.page as usize].text.clone();
             self.scroll_offset = 0;
+            self.ruler_line = 0;
+            self.selection = None;
+        } else if self.queue.iter().any(|queued| *queued != self.path) {
+            self.open_queue(true);
         }
     }
 
     /// This will handle going to the previous page, with 0 also being the lowest possible page
     fn previous_page(&mut self) {
-        if self.page != 0 {
-            self.page -= 1;
-            self.text = self.content[self.page as usize].clone();
+        let previous = (0..self.page).rev().find(|&page| !self.skips(page));
+        if let Some(page) = previous {
+            self.page = page;
+            self.text = self.content[self.page as usize].text.clone();
             self.scroll_offset = 0;
+            self.ruler_line = 0;
+            self.selection = None;
+        }
+    }
+
+    /// Whether Left/Right navigation should step over the given page
+    fn skips(&self, page: u16) -> bool {
+        self.empty_pages == EmptyPages::Skip
+            && self.content[page as usize].is_filler(self.min_words)
+    }
+
+    /// Spine index of the current page, this is what progress is stored as
+    /// so that saved positions survive changing the empty page handling
+    fn spine_index(&self) -> u16 {
+        self.spine_pages
+            .get(self.page as usize)
+            .map_or(self.page, |&spine_index| spine_index as u16)
+    }
+
+    /// Current position as it is saved to the progress file
+    fn position(&self) -> ReadingPosition {
+        ReadingPosition::new(self.spine_index(), self.scroll_offset, &self.text)
+    }
+
+    /// Fraction of the book read, weighting every page by its line count
+    /// so that scrolling within a long page advances it smoothly
+    fn fraction_read(&self) -> f64 {
+        let total: usize = self.line_counts.iter().sum();
+        if total == 0 {
+            return 0.0;
         }
+        let before: usize = self.line_counts.iter().take(self.page as usize).sum();
+        let current = self
+            .line_counts
+            .get(self.page as usize)
+            .copied()
+            .unwrap_or(0);
+        let within = (self.scroll_offset as usize).min(current);
+        // Count the last page as finished once it is scrolled to the bottom
+        let read = if self.page + 1 == self.pages && self.scroll_offset >= self.max_scroll_offset()
+        {
+            total
+        } else {
+            before + within
+        };
+        read as f64 / total as f64
+    }
+
+    /// Page that contains the given spine index
+    fn page_for_spine(&self, spine_index: usize) -> u16 {
+        let page = self
+            .spine_pages
+            .partition_point(|&start| start <= spine_index)
+            .saturating_sub(1);
+        page.min(self.pages.saturating_sub(1) as usize) as u16
     }
 
     /// Scroll up on the current page by decreasing the scroll offset, if not at top of page
+    /// In continuous mode the top of the page scrolls on into the bottom of the previous page
     fn scroll_up(&mut self) {
         if self.scroll_offset > 0 {
             self.scroll_offset -= 1;
+        } else if self.config.continuous {
+            let page = self.page;
+            self.previous_page();
+            if self.page != page {
+                self.scroll_offset = self.max_scroll_offset();
+                self.ruler_line = self.scroll_offset;
+            }
         }
     }
 
-    /// Scroll down on the current page by increasing the scroll offset
+    /// Scroll down on the current page by increasing the scroll offset, if not at bottom of page
+    /// In continuous mode the bottom of the page scrolls on into the next page
     fn scroll_down(&mut self) {
-        self.scroll_offset += 1;
+        if self.scroll_offset < self.max_scroll_offset() {
+            self.scroll_offset += 1;
+        } else if self.config.continuous {
+            self.next_page();
+        }
     }
 
-    /// Loads progress from a file
-    fn load_progress(&mut self) {
-        if let Ok(data) = fs::read_to_string("progress.json") {
-            self.progress = serde_json::from_str(&data).unwrap_or_default();
+    /// Scroll up by a screenful, at the top of the page go to the bottom of the previous page
+    fn screen_up(&mut self) {
+        if self.config.continuous {
+            self.scroll_lines(false);
+            return;
+        }
+        if self.scroll_offset > 0 {
+            let before: Vec<&str> = self
+                .text
+                .lines()
+                .take(self.scroll_offset as usize)
+                .collect();
+            self.scroll_offset -= self.lines_fitting(before.into_iter().rev());
+            return;
+        }
+        let page = self.page;
+        self.previous_page();
+        if self.page != page {
+            self.scroll_offset = self.max_scroll_offset();
+            self.ruler_line = self.scroll_offset;
         }
     }
 
-    /// Saves progress to a file
-    fn save_progress(&self) {
-        let data = serde_json::to_string(&self.progress).unwrap();
-        fs::write("progress.json", data).unwrap();
+    /// Scroll down by a screenful, at the bottom of the page go to the next page
+    fn screen_down(&mut self) {
+        if self.config.continuous {
+            self.scroll_lines(true);
+            return;
+        }
+        let max_offset = self.max_scroll_offset();
+        if self.scroll_offset < max_offset {
+            let on_screen = self.lines_fitting(self.text.lines().skip(self.scroll_offset as usize));
+            self.scroll_offset = (self.scroll_offset + on_screen).min(max_offset);
+        } else {
+            self.next_page();
+        }
     }
 
-    /// Calculate the estimated reading time for the current page based on WPM
-    fn calculate_reading_time(&self) -> u32 {
-        let word_count = self.text.split_whitespace().count();
-        let reading_time = (word_count as f32 / self.wpm as f32 * 60.0).ceil() as u32;
-        reading_time
+    /// Scrolls a screenful one line at a time, so continuous mode rolls over between pages
+    /// Stops before a line that wouldn't have been fully on screen
+    fn scroll_lines(&mut self, down: bool) {
+        let height = self.view_height.get().max(1);
+        let mut rows = 0;
+        loop {
+            let position = (self.page, self.scroll_offset);
+            let leaving = self.top_line_rows();
+            if down && rows > 0 && rows + leaving > height {
+                break;
+            }
+            if down {
+                self.scroll_down();
+            } else {
+                self.scroll_up();
+            }
+            if (self.page, self.scroll_offset) == position || self.show_queue {
+                break;
+            }
+            let moved = if down { leaving } else { self.top_line_rows() };
+            if !down && rows > 0 && rows + moved > height {
+                self.scroll_down();
+                break;
+            }
+            rows += moved;
+        }
     }
 
-    /// Show the estimated reading time for the current page in a popup
-    fn show_reading_time(&mut self) {
-        let reading_time = self.calculate_reading_time();
-        self.popup_text = Some(format!(
-            "Estimated reading time: {} seconds (WPM: {}) \n\n\n Press <C> to close pop-up!",
-            reading_time, self.wpm
-        ));
+    /// Largest scroll offset that still shows the end of the page, wrapped lines taking up
+    /// as many rows as they are drawn on
+    /// In continuous mode a page followed by another scrolls until its last line is on top
+    fn max_scroll_offset(&self) -> u16 {
+        let line_count = self.text.lines().count() as u16;
+        if self.config.continuous && self.following_pages().next().is_some() {
+            return line_count.saturating_sub(1);
+        }
+        line_count.saturating_sub(self.lines_fitting(self.text.lines().rev()))
     }
 
-    /// Shows the document's metadata (e.g., author, title) in a popup
-    fn show_metadata(&mut self) {
-        let metadata_str = self.format_metadata();
-        self.show_metadata = Some(metadata_str);
+    /// How many of `lines` fit on screen together once wrapped, at least one
+    fn lines_fitting<'a>(&self, lines: impl Iterator<Item = &'a str>) -> u16 {
+        let height = self.view_height.get().max(1);
+        let mut rows = 0;
+        let mut count = 0;
+        for line in lines {
+            rows += self.line_rows(line);
+            if rows > height {
+                break;
+            }
+            count += 1;
+        }
+        count.max(1)
     }
 
-    /// Helper function to format the metadata as a string
-    fn format_metadata(&self) -> String {
-        let mut result = String::new();
-        for (key, values) in &self.metadata {
-            result.push_str(&format!("\n {}:  {}\n", key, values.join(", ")));
+    /// Rows the top visible line takes up once wrapped
+    fn top_line_rows(&self) -> u16 {
+        self.text
+            .lines()
+            .nth(self.scroll_offset as usize)
+            .map_or(1, |line| self.line_rows(line))
+    }
+
+    /// Rows a line of the page takes up once wrapped to the width of the text column
+    fn line_rows(&self, line: &str) -> u16 {
+        let width = self.view_width.get();
+        if width == 0 {
+            return 1;
         }
-        result.push_str(&format!("\n\n\nPress <C> to close pop-up!"));
-        result
+        Paragraph::new(self.display_line(line))
+            .wrap(TEXT_WRAP)
+            .line_count(width)
+            .max(1) as u16
     }
-}
 
-impl Widget for &App {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let title = Title::from(" Epub reader application ".bold());
-        let instructions = Title::from(Line::from(vec![
-            " Previous page ".into(),
-            "<Left>".blue().bold(),
-            " Next page ".into(),
-            "<Right>".blue().bold(),
-            " Scroll up ".into(),
-            "<Up>".blue().bold(),
-            " Scroll down ".into(),
-            "<Down>".blue().bold(),
-            " Bonus ".into(),
-            "<S> ".blue().bold(),
-            " Metadata ".into(),
-            "<M> ".blue().bold(),
-            " Quit ".into(),
-            "<Q> ".blue().bold(),
-        ]));
-        let block = Block::bordered()
-            .title(title.alignment(Alignment::Center))
-            .title(
-                instructions
-                    .alignment(Alignment::Center)
-                    .position(Position::Bottom),
-            )
-            .border_set(border::THICK);
+    /// A line of the page as it is shown, with plain punctuation if asked for
+    fn display_line(&self, line: &str) -> String {
+        if self.ascii_punctuation {
+            text::ascii_punctuation(line)
+        } else {
+            line.to_string()
+        }
+    }
 
-        let text_lines: Vec<Line> = self
+    /// Pages after the current one that Left/Right navigation doesn't step over
+    fn following_pages(&self) -> impl Iterator<Item = u16> + '_ {
+        (self.page + 1..self.pages).filter(|&page| !self.skips(page))
+    }
+
+    /// Switches between turning pages and scrolling straight through the book, and remembers the choice
+    fn toggle_continuous(&mut self) {
+        self.config.continuous = !self.config.continuous;
+        self.scroll_offset = self.scroll_offset.min(self.max_scroll_offset());
+        let saved = self.config.save();
+        self.report(saved);
+    }
+
+    /// Schedules auto-scroll to move on once the top visible line has been read at the current WPM
+    fn schedule_auto_scroll(&mut self) {
+        let words = self
             .text
-            .lines() // Split text by newlines
-            .skip(self.scroll_offset as usize) // Skip lines based on scroll_offset
-            .take(area.height as usize) // Take only the visible lines
-            .map(|line| Line::from(line.to_string().yellow()))
-            .collect();
+            .lines()
+            .nth(self.scroll_offset as usize)
+            .map_or(0, |line| line.split_whitespace().count());
+        let interval = Duration::from_secs_f32(words as f32 / self.wpm as f32 * 60.0);
+        self.auto_scroll = Some(Instant::now() + interval.max(MIN_AUTO_SCROLL_INTERVAL));
+    }
 
-        let test_text = Text::from(text_lines);
+    /// Scrolls down a line for auto-scroll, turning the page at the bottom
+    /// Auto-scroll stops at the end of the book
+    fn auto_scroll_tick(&mut self) {
+        let position = (self.page, self.scroll_offset);
+        self.scroll_down();
+        if (self.page, self.scroll_offset) == position {
+            self.next_page();
+        }
+        if (self.page, self.scroll_offset) == position || self.show_queue {
+            self.auto_scroll = None;
+        } else {
+            self.schedule_auto_scroll();
+        }
+    }
 
-        let _counter_text = Text::from(vec![
-            Line::from(vec!["Page: ".into(), self.page.to_string().yellow()]),
-            Line::from(vec!["Path: ".into(), self.path.clone().yellow()]),
-            Line::from(vec!["text: ".into(), self.text.clone().yellow()]),
-        ]);
+    /// Shows or hides the reading ruler, pinning it to the top visible line
+    fn toggle_ruler(&mut self) {
+        self.ruler = !self.ruler;
+        self.ruler_line = self.scroll_offset;
+    }
 
-        Paragraph::new(test_text)
-            .wrap(Wrap { trim: true })
-            .block(block)
-            .render(area, buf);
+    /// Switches between a fixed ruler and one that follows the text, and remembers the choice
+    fn toggle_ruler_mode(&mut self) {
+        self.config.ruler_mode = self.config.ruler_mode.toggled();
+        self.ruler_line = self.scroll_offset;
+        let saved = self.config.save();
+        self.report(saved);
     }
-}
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
+    /// Switches to the next color theme, and remembers the choice for this book and as the default
+    fn toggle_theme(&mut self) {
+        self.theme = self.theme.toggled();
+        self.config.theme = self.theme;
+        self.overrides.theme = None;
+        let theme = self.theme;
+        self.update_book_settings(|settings| settings.theme = Some(theme));
+        let saved = self.config.save();
+        self.report(saved);
+    }
 
-    let mut terminal = ratatui::init();
-    terminal.clear()?;
-    let app_result = App::default().run(&mut terminal, args);
-    ratatui::restore();
-    app_result
+    /// Speeds up or slows down the reading speed used for estimates, and remembers it for this
+    /// book and as the default. An open reading time popup is updated right away
+    fn change_wpm(&mut self, change: i32) {
+        self.wpm = (self.wpm as i32 + change).clamp(MIN_WPM as i32, MAX_WPM as i32) as u16;
+        self.config.words_per_minute = self.wpm;
+        self.overrides.words_per_minute = None;
+        let wpm = self.wpm;
+        self.update_book_settings(|settings| settings.words_per_minute = Some(wpm));
+        if self.popup_text.is_some() && self.popup_title == "Reading Time" {
+            self.show_reading_time();
+        }
+        let saved = self.config.save();
+        self.report(saved);
+    }
+
+    /// Narrows or widens the text column, and remembers the choice for this book and as the default
+    fn change_reading_width(&mut self, change: i32) {
+        let width = self.reading_width as i32 + change;
+        self.reading_width = width.clamp(MIN_READING_WIDTH as i32, MAX_READING_WIDTH as i32) as u16;
+        self.config.reading_width = self.reading_width;
+        let reading_width = self.reading_width;
+        self.update_book_settings(|settings| settings.reading_width = Some(reading_width));
+        let saved = self.config.save();
+        self.report(saved);
+    }
+
+    /// Starts selecting lines from the ruler, or the top visible line, or drops the selection
+    fn toggle_selection(&mut self) {
+        if self.selection.is_some() {
+            self.selection = None;
+            return;
+        }
+        let start = if self.ruler && self.config.ruler_mode == RulerMode::Follow {
+            self.ruler_line
+        } else {
+            self.scroll_offset
+        };
+        self.selection = Some((start, start));
+    }
+
+    /// Moves the end of the selection up a line, scrolling to keep it visible
+    fn extend_selection_up(&mut self) {
+        if let Some((anchor, end)) = self.selection {
+            let end = end.saturating_sub(1);
+            self.selection = Some((anchor, end));
+            self.scroll_offset = self.scroll_offset.min(end);
+        }
+    }
+
+    /// Moves the end of the selection down a line, scrolling to keep it visible
+    fn extend_selection_down(&mut self) {
+        if let Some((anchor, end)) = self.selection {
+            let last_line = self.text.lines().count().saturating_sub(1) as u16;
+            let end = (end + 1).min(last_line);
+            self.selection = Some((anchor, end));
+            let height = self.view_height.get().max(1);
+            if end >= self.scroll_offset + height {
+                self.scroll_offset = end + 1 - height;
+            }
+        }
+    }
+
+    /// Text of the selected lines
+    fn selected_text(&self) -> Option<String> {
+        let (anchor, end) = self.selection?;
+        let (first, last) = (anchor.min(end) as usize, anchor.max(end) as usize);
+        let lines: Vec<&str> = self
+            .text
+            .lines()
+            .skip(first)
+            .take(last - first + 1)
+            .map(str::trim)
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    /// Handles the text entered into an input line
+    fn submit_input(&mut self, input: Input) {
+        match input.prompt {
+            Prompt::Note => self.add_annotation(input.value),
+            Prompt::Search => self.search(input.value),
+            Prompt::GoToPage => self.go_to_page(&input.value),
+            Prompt::Footnote => self.show_footnote(&input.value),
+            Prompt::Lookup => self.look_up(&input.value),
+        }
+    }
+
+    /// Finds every line of the book containing `query`, ignoring case,
+    /// and jumps to the first match at or after the current position
+    /// Lines are matched as they are displayed, so the hits agree with the highlighting
+    fn search(&mut self, query: String) {
+        let needle = self.search_needle(&query);
+        if needle.is_empty() {
+            self.search_query = None;
+            self.search_hits.clear();
+            return;
+        }
+        self.search_hits = self
+            .content
+            .iter()
+            .enumerate()
+            .flat_map(|(page, content)| {
+                content
+                    .text
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| {
+                        !text::find_matches(&self.display_line(line), &needle).is_empty()
+                    })
+                    .map(move |(line, _)| (page, line))
+            })
+            .collect();
+        self.search_query = Some(query);
+
+        if self.search_hits.is_empty() {
+            self.popup_title = "Search";
+            self.popup_text = Some("No matches\n\n\n Press <C> to close pop-up!".to_string());
+            return;
+        }
+        let here = (self.page as usize, self.scroll_offset as usize);
+        self.search_index = self
+            .search_hits
+            .iter()
+            .position(|&hit| hit >= here)
+            .unwrap_or(0);
+        self.show_match();
+    }
+
+    /// The query as it is looked for in displayed lines, lowercase and with the same
+    /// punctuation they have
+    fn search_needle(&self, query: &str) -> String {
+        self.display_line(query).to_lowercase()
+    }
+
+    /// Splits a line into spans so the matches of the active search stand out
+    fn highlight_matches(&self, line: String, style: Style) -> Vec<Span<'static>> {
+        let needle = self
+            .search_query
+            .as_ref()
+            .map(|query| self.search_needle(query))
+            .unwrap_or_default();
+        let mut spans = Vec::new();
+        let mut end = 0;
+        for range in text::find_matches(&line, &needle) {
+            if range.start > end {
+                spans.push(Span::styled(line[end..range.start].to_string(), style));
+            }
+            spans.push(Span::styled(
+                line[range.clone()].to_string(),
+                style.patch(MATCH_STYLE),
+            ));
+            end = range.end;
+        }
+        if end < line.len() || spans.is_empty() {
+            spans.push(Span::styled(line[end..].to_string(), style));
+        }
+        spans
+    }
+
+    /// Jumps to the next search match, wrapping around to the start of the book
+    fn next_match(&mut self) {
+        if !self.search_hits.is_empty() {
+            self.search_index = (self.search_index + 1) % self.search_hits.len();
+            self.show_match();
+        }
+    }
+
+    /// Jumps to the previous search match, wrapping around to the end of the book
+    fn previous_match(&mut self) {
+        if !self.search_hits.is_empty() {
+            let count = self.search_hits.len();
+            self.search_index = (self.search_index + count - 1) % count;
+            self.show_match();
+        }
+    }
+
+    /// Scrolls so the current search match is the top visible line
+    fn show_match(&mut self) {
+        let (page, line) = self.search_hits[self.search_index];
+        self.jump_to(page as u16, line as u16);
+    }
+
+    /// Attaches a note to the selected passage and saves it
+    fn add_annotation(&mut self, note: String) {
+        let (Some(quote), Some((anchor, end))) = (self.selected_text(), self.selection) else {
+            return;
+        };
+        let annotation = Annotation {
+            page: self.spine_index(),
+            line: anchor.min(end),
+            chapter: self.chapter_title(),
+            quote,
+            note,
+        };
+        self.notes
+            .entry(self.path.clone())
+            .or_default()
+            .push(annotation);
+        let saved = notes::save(&self.notes);
+        self.report(saved);
+        self.selection = None;
+    }
+
+    /// Shows the reading queue, `finished` highlights the next book after the current one
+    fn open_queue(&mut self, finished: bool) {
+        self.show_queue = true;
+        self.finished = finished;
+        self.queue_index = if finished {
+            self.queue
+                .iter()
+                .position(|queued| *queued != self.path)
+                .unwrap_or(0)
+        } else {
+            0
+        };
+    }
+
+    /// Handles keys while the reading queue is shown
+    fn handle_queue_key_event(&mut self, key_event: KeyEvent) {
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        let last = self.queue.len().saturating_sub(1);
+        match key_event.code {
+            KeyCode::Up if shift && self.queue_index > 0 => {
+                self.queue.swap(self.queue_index, self.queue_index - 1);
+                self.queue_index -= 1;
+                self.save_queue();
+            }
+            KeyCode::Down if shift && self.queue_index < last => {
+                self.queue.swap(self.queue_index, self.queue_index + 1);
+                self.queue_index += 1;
+                self.save_queue();
+            }
+            KeyCode::Up => self.queue_index = self.queue_index.saturating_sub(1),
+            KeyCode::Down => self.queue_index = (self.queue_index + 1).min(last),
+            KeyCode::Char('a') => self.queue_current_book(),
+            KeyCode::Char('d') if !self.queue.is_empty() => {
+                self.queue.remove(self.queue_index);
+                self.queue_index = self.queue_index.min(self.queue.len().saturating_sub(1));
+                self.save_queue();
+            }
+            KeyCode::Enter => self.open_queued_book(),
+            KeyCode::Char('c') | KeyCode::Esc if self.popup_text.is_some() => {
+                self.popup_text = None
+            }
+            KeyCode::Char('c') | KeyCode::Esc | KeyCode::Char('u') => self.show_queue = false,
+            _ => {}
+        }
+    }
+
+    /// Adds the open book to the end of the reading queue
+    fn queue_current_book(&mut self) {
+        if queue::add(&mut self.queue, &self.path) {
+            self.save_queue();
+        }
+    }
+
+    /// Opens the highlighted book from the queue, a finished book leaves the queue
+    fn open_queued_book(&mut self) {
+        let Some(path) = self.queue.get(self.queue_index).cloned() else {
+            return;
+        };
+        if path == self.path {
+            self.show_queue = false;
+            return;
+        }
+        let epub = match book::open(&path) {
+            Ok(epub) => epub,
+            Err(error) => {
+                self.popup_title = "Reading Queue";
+                self.popup_text = Some(format!("{}\n\nRemove it from the queue with <D>.", error));
+                return;
+            }
+        };
+
+        if self.finished {
+            self.queue.retain(|queued| *queued != self.path);
+        }
+        self.queue.retain(|queued| *queued != path);
+        self.show_queue = false;
+        self.popup_text = None;
+        self.save_queue();
+        self.save_progress();
+        self.load_book(path, epub);
+    }
+
+    /// Shows the recently read books to pick one from
+    fn open_library(&mut self) {
+        self.update_library();
+        self.recent_books = library::recent(self.progress.keys(), &self.library);
+        self.library_index = 0;
+        self.show_library = true;
+    }
+
+    /// Handles keys while the library picker is shown
+    /// The picker can only be closed once a book is open, quitting always works
+    fn handle_library_key_event(&mut self, key_event: KeyEvent) {
+        let last = self.recent_books.len().saturating_sub(1);
+        match key_event.code {
+            KeyCode::Up => self.library_index = self.library_index.saturating_sub(1),
+            KeyCode::Down => self.library_index = (self.library_index + 1).min(last),
+            KeyCode::Enter => self.open_recent_book(),
+            KeyCode::Char('c') | KeyCode::Esc if self.popup_text.is_some() => {
+                self.popup_text = None
+            }
+            KeyCode::Char('c') | KeyCode::Esc | KeyCode::Char('o') if !self.path.is_empty() => {
+                self.show_library = false
+            }
+            code if self.config.keys.action(code) == Some(Action::Quit) => self.exit(),
+            _ => {}
+        }
+    }
+
+    /// Opens the book selected in the library picker
+    /// Books that can't be opened are reported and the picker stays open
+    fn open_recent_book(&mut self) {
+        let Some(path) = self.recent_books.get(self.library_index).cloned() else {
+            return;
+        };
+        if path == self.path {
+            self.show_library = false;
+            return;
+        }
+        let epub = match book::open(&path) {
+            Ok(epub) => epub,
+            Err(error) => {
+                self.popup_title = "Recent Books";
+                self.popup_text = Some(error.to_string());
+                return;
+            }
+        };
+        self.show_library = false;
+        self.popup_text = None;
+        self.save_progress();
+        self.load_book(path, epub);
+    }
+
+    /// Remembers the title and progress of the open book for the library picker
+    /// Only done when a book is opened or closed, and when the picker is shown, so scrolling
+    /// doesn't rewrite the library file
+    fn update_library(&mut self) {
+        if self.path.is_empty() {
+            return;
+        }
+        let title = self
+            .metadata
+            .get("title")
+            .and_then(|titles| titles.first())
+            .cloned()
+            .unwrap_or_default();
+        let percent = (self.fraction_read() * 100.0).round() as u8;
+        self.library
+            .insert(self.path.clone(), BookInfo::new(title, percent));
+        let saved = library::save(&self.library);
+        self.report(saved);
+    }
+
+    fn render_library(&self, frame: &mut Frame) {
+        let popup_area = centered_rect(70, 60, frame.area());
+        self.clear_popup(frame, popup_area);
+
+        let hints = if self.path.is_empty() {
+            " <Enter> open <q> quit "
+        } else {
+            " <Enter> open <c> close "
+        };
+        let hints = Title::from(hints)
+            .alignment(Alignment::Center)
+            .position(Position::Bottom);
+        let block = Block::default()
+            .title(" Recent Books ")
+            .title(hints)
+            .borders(Borders::ALL);
+
+        if self.recent_books.is_empty() {
+            let empty = Paragraph::new("No books read yet, open one with --path.")
+                .wrap(Wrap { trim: true })
+                .block(block);
+            frame.render_widget(empty, popup_area);
+            return;
+        }
+
+        let items: Vec<String> = self
+            .recent_books
+            .iter()
+            .map(|path| match self.library.get(path) {
+                Some(info) if !info.title.is_empty() => {
+                    let missing = if Path::new(path).exists() {
+                        ""
+                    } else {
+                        " (missing)"
+                    };
+                    format!("{}{}  {}%", info.title, missing, info.percent)
+                }
+                _ => queue::label(path),
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.library_index));
+        StatefulWidget::render(list, popup_area, frame.buffer_mut(), &mut state);
+    }
+
+    /// Saves the reading queue, reporting failures in a popup
+    fn save_queue(&mut self) {
+        let saved = queue::save(&self.queue);
+        self.report(saved);
+    }
+
+    /// Shows a failed operation in a popup instead of ending the session
+    fn report(&mut self, result: Result<(), Error>) {
+        if let Err(error) = result {
+            self.popup_title = "Error";
+            self.popup_text = Some(format!("{}\n\n\n Press <C> to close pop-up!", error));
+        }
+    }
+
+    /// Renders the reading queue as a navigable list
+    fn render_queue(&self, frame: &mut Frame) {
+        let popup_area = centered_rect(60, 60, frame.area());
+        self.clear_popup(frame, popup_area);
+
+        let title = if self.finished {
+            " Finished! Up next "
+        } else {
+            " Reading Queue "
+        };
+        let hints = Title::from(" <Enter> open <Shift+Up/Down> move <A> add current <D> remove ")
+            .alignment(Alignment::Center)
+            .position(Position::Bottom);
+        let block = Block::default()
+            .title(title)
+            .title(hints)
+            .borders(Borders::ALL);
+
+        if self.queue.is_empty() {
+            let empty = Paragraph::new("The queue is empty, press <A> to add this book.")
+                .wrap(Wrap { trim: true })
+                .block(block);
+            frame.render_widget(empty, popup_area);
+            return;
+        }
+
+        let items: Vec<String> = self
+            .queue
+            .iter()
+            .enumerate()
+            .map(|(i, path)| format!("{}. {}", i + 1, queue::label(path)))
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.queue_index));
+        StatefulWidget::render(list, popup_area, frame.buffer_mut(), &mut state);
+    }
+
+    /// Shows the table of contents with the current chapter highlighted
+    fn open_toc(&mut self) {
+        self.toc_index = self.current_toc_index().unwrap_or(0);
+        self.show_toc = true;
+    }
+
+    /// Handles keys while the table of contents is shown
+    fn handle_toc_key_event(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Up => self.toc_index = self.toc_index.saturating_sub(1),
+            KeyCode::Down => {
+                self.toc_index = (self.toc_index + 1).min(self.toc.len().saturating_sub(1))
+            }
+            KeyCode::Enter => {
+                let target = self
+                    .toc
+                    .get(self.toc_index)
+                    .and_then(|entry| entry.spine_index);
+                if let Some(spine_index) = target {
+                    self.jump_to(self.page_for_spine(spine_index), 0);
+                    self.show_toc = false;
+                }
+            }
+            KeyCode::Char('c') | KeyCode::Esc | KeyCode::Char('t') => self.show_toc = false,
+            _ => {}
+        }
+    }
+
+    /// Shows the cover of the book, if it has one that could be decoded
+    fn show_cover(&mut self) {
+        if self.cover.is_some() {
+            self.show_cover = true;
+        } else {
+            self.popup_title = " Cover ";
+            self.popup_text = Some("This book has no cover image.".to_string());
+        }
+    }
+
+    /// Bookmarks the current page and scroll offset
+    fn add_bookmark(&mut self) {
+        let position = self.position();
+        let added = bookmarks::add(
+            self.bookmarks.entry(self.path.clone()).or_default(),
+            position,
+        );
+        if added {
+            let saved = bookmarks::save(&self.bookmarks);
+            self.report(saved);
+        }
+        self.popup_title = " Bookmarks ";
+        self.popup_text = Some(if added {
+            "Bookmark added.".to_string()
+        } else {
+            "This position is already bookmarked.".to_string()
+        });
+    }
+
+    /// Shows the bookmarks of the current book, highlighting the last one before the current position
+    fn open_bookmarks(&mut self) {
+        let position = self.position();
+        self.bookmark_index = self
+            .bookmarks
+            .get(&self.path)
+            .and_then(|marks| {
+                marks
+                    .iter()
+                    .rposition(|mark| (mark.page, mark.offset) <= (position.page, position.offset))
+            })
+            .unwrap_or(0);
+        self.show_bookmarks = true;
+    }
+
+    /// Handles keys while the bookmark list is shown
+    fn handle_bookmarks_key_event(&mut self, key_event: KeyEvent) {
+        let count = self.bookmarks.get(&self.path).map_or(0, Vec::len);
+        match key_event.code {
+            KeyCode::Up => self.bookmark_index = self.bookmark_index.saturating_sub(1),
+            KeyCode::Down => {
+                self.bookmark_index = (self.bookmark_index + 1).min(count.saturating_sub(1))
+            }
+            KeyCode::Enter => {
+                let target = self
+                    .bookmarks
+                    .get(&self.path)
+                    .and_then(|marks| marks.get(self.bookmark_index))
+                    .cloned();
+                if let Some(mark) = target {
+                    self.go_to_position(&mark);
+                    self.show_bookmarks = false;
+                }
+            }
+            KeyCode::Char('d') if self.bookmark_index < count => {
+                if let Some(marks) = self.bookmarks.get_mut(&self.path) {
+                    marks.remove(self.bookmark_index);
+                }
+                self.bookmark_index = self.bookmark_index.min(count.saturating_sub(2));
+                let saved = bookmarks::save(&self.bookmarks);
+                self.report(saved);
+            }
+            KeyCode::Char('c') | KeyCode::Esc | KeyCode::Char('B') => self.show_bookmarks = false,
+            _ => {}
+        }
+    }
+
+    /// Links of the current page
+    fn links(&self) -> &[Link] {
+        self.content
+            .get(self.page as usize)
+            .map(|page| page.links.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Handles keys while the list of links on the page is shown
+    fn handle_links_key_event(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Up => self.link_index = self.link_index.saturating_sub(1),
+            KeyCode::Down => {
+                self.link_index = (self.link_index + 1).min(self.links().len().saturating_sub(1))
+            }
+            KeyCode::Enter => {
+                if let Some(link) = self.links().get(self.link_index).cloned() {
+                    self.show_links = false;
+                    self.follow_link(&link);
+                }
+            }
+            KeyCode::Char('c') | KeyCode::Esc | KeyCode::Char('f') => self.show_links = false,
+            _ => {}
+        }
+    }
+
+    /// Jumps to the target of an internal link, external links are only shown
+    fn follow_link(&mut self, link: &Link) {
+        match link.spine_index {
+            Some(spine_index) => {
+                let page = self.page_for_spine(spine_index);
+                let line = link
+                    .anchor()
+                    .and_then(|anchor| self.content[page as usize].anchor_line(anchor))
+                    .unwrap_or(0);
+                self.jump_to(page, line as u16);
+            }
+            None => {
+                self.popup_title = " Link ";
+                self.popup_text = Some(if link.is_external() {
+                    format!(
+                        "{}\n\nExternal links can't be opened from the reader.",
+                        link.href
+                    )
+                } else {
+                    format!(
+                        "{}\n\nThe target of this link isn't part of the book.",
+                        link.href
+                    )
+                });
+            }
+        }
+    }
+
+    /// Shows the text a `[n]` marker on the page points at, without leaving the page
+    /// Input that isn't a number is ignored
+    fn show_footnote(&mut self, value: &str) {
+        let Ok(number) = value.trim().parse::<usize>() else {
+            return;
+        };
+        let Some(link) = number
+            .checked_sub(1)
+            .and_then(|i| self.links().get(i).cloned())
+        else {
+            self.popup_title = " Footnote ";
+            self.popup_text = Some(format!("There is no [{}] on this page.", number));
+            return;
+        };
+        let note = link
+            .spine_index
+            .zip(link.anchor())
+            .and_then(|(spine_index, anchor)| {
+                let page = &self.content[self.page_for_spine(spine_index) as usize];
+                page.anchor_line(anchor).map(|line| page.block_at(line))
+            });
+        match note.filter(|note| !note.is_empty()) {
+            Some(note) if self.ascii_punctuation => {
+                self.footnote = Some((number, text::ascii_punctuation(&note)))
+            }
+            Some(note) => self.footnote = Some((number, note)),
+            None => {
+                self.popup_title = " Footnote ";
+                self.popup_text = Some(format!(
+                    "[{}] {} doesn't point at a note in the book.",
+                    number, link.href
+                ));
+            }
+        }
+    }
+
+    /// Starts looking a word up in the background, the page stays usable while it runs
+    fn look_up(&mut self, word: &str) {
+        let word = word.trim();
+        if word.is_empty() {
+            return;
+        }
+        self.popup_title = " Dictionary ";
+        if self.dictionary.is_empty() {
+            self.popup_text = Some(
+                "No dictionary, add dictionary.json to the config directory or use --dictionary-url."
+                    .to_string(),
+            );
+            return;
+        }
+        self.definition = None;
+        self.popup_text = Some(format!("Looking up \"{}\"...", word));
+        self.lookup = Some(Lookup::start(
+            Arc::clone(&self.dictionary),
+            word.to_string(),
+        ));
+    }
+
+    /// Shows the definitions found by a finished lookup, or why there are none
+    fn finish_lookup(&mut self) {
+        let Some(lookup) = self.lookup.take() else {
+            return;
+        };
+        let word = lookup.word.clone();
+        self.popup_title = " Dictionary ";
+        self.popup_text = None;
+        match lookup.finish() {
+            Ok(Some(definitions)) => {
+                let definition = definitions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, definition)| format!("{}. {}", i + 1, definition))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                self.definition = Some((word, definition));
+            }
+            Ok(None) => {
+                self.popup_text = Some(format!("No definition found for \"{}\".", word));
+            }
+            Err(error) => self.report(Err(error)),
+        }
+    }
+
+    /// Jumps to a page number counted from 1, clamped to the pages of the book
+    /// Input that isn't a number is ignored
+    fn go_to_page(&mut self, value: &str) {
+        if let Ok(number) = value.trim().parse::<u32>() {
+            let page = number.saturating_sub(1).min(u16::MAX as u32) as u16;
+            self.jump_to(page, 0);
+        }
+    }
+
+    /// Moves to a saved position, landing on the line it was saved on even if the page's lines shifted
+    fn go_to_position(&mut self, position: &ReadingPosition) {
+        let page = self.page_for_spine(position.page as usize);
+        let offset = self
+            .content
+            .get(page as usize)
+            .map_or(position.offset, |content| position.offset_in(&content.text));
+        self.jump_to(page, offset);
+    }
+
+    /// Moves to a page and line, dropping any selection
+    fn jump_to(&mut self, page: u16, offset: u16) {
+        self.page = page.min(self.pages.saturating_sub(1));
+        self.text = self.content[self.page as usize].text.clone();
+        let last_line = self.text.lines().count().saturating_sub(1) as u16;
+        self.scroll_offset = offset.min(last_line);
+        self.ruler_line = self.scroll_offset;
+        self.selection = None;
+    }
+
+    /// Renders the bookmarks of the open book as a navigable list
+    fn render_bookmarks(&self, frame: &mut Frame) {
+        let popup_area = centered_rect(60, 70, frame.area());
+        self.clear_popup(frame, popup_area);
+        let block = Block::default()
+            .title(" Bookmarks ")
+            .title(
+                Title::from(" <Enter> jump <D> delete <C> close ")
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL);
+
+        let marks = self
+            .bookmarks
+            .get(&self.path)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if marks.is_empty() {
+            let empty =
+                Paragraph::new("No bookmarks yet, press b while reading to add one.").block(block);
+            frame.render_widget(empty, popup_area);
+            return;
+        }
+
+        let items: Vec<Line> = marks
+            .iter()
+            .map(|mark| {
+                let page = self.page_for_spine(mark.page as usize);
+                // The first line with text at the bookmark, as a reminder of the passage
+                let snippet = self
+                    .content
+                    .get(page as usize)
+                    .and_then(|content| {
+                        content
+                            .text
+                            .lines()
+                            .skip(mark.offset as usize)
+                            .find(|line| !line.trim().is_empty())
+                    })
+                    .unwrap_or_default()
+                    .trim();
+                Line::from(vec![
+                    format!("Page {:<5}", page + 1).bold(),
+                    snippet.to_string().into(),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.bookmark_index));
+        StatefulWidget::render(list, popup_area, frame.buffer_mut(), &mut state);
+    }
+
+    /// Every key of the reader grouped by category, using the configured keys where they apply
+    fn help_sections(&self) -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+        let keys = &self.config.keys;
+        let key = |key: &str| key.to_string();
+        vec![
+            (
+                "Navigation",
+                vec![
+                    (keys.label(Action::PreviousPage), "Previous page"),
+                    (keys.label(Action::NextPage), "Next page"),
+                    (keys.label(Action::ScrollUp), "Scroll up"),
+                    (keys.label(Action::ScrollDown), "Scroll down"),
+                    (keys.label(Action::ScreenUp), "Scroll up a screen"),
+                    (keys.label(Action::ScreenDown), "Scroll down a screen"),
+                    (key("<g>"), "Go to page"),
+                    (key("<t>"), "Table of contents"),
+                    (key("<f>"), "Links on this page"),
+                    (key("<F>"), "Show a footnote by number"),
+                    (key("<b> / <B>"), "Add bookmark / list bookmarks"),
+                    (key("<G>"), "Show locator"),
+                    (key("<u>"), "Reading queue"),
+                    (key("<o>"), "Recently read books"),
+                ],
+            ),
+            (
+                "View",
+                vec![
+                    (key("<r> / <R>"), "Toggle ruler / ruler mode"),
+                    (key("<[> / <]>"), "Narrower / wider text"),
+                    (key("<p>"), "Next color theme"),
+                    (key("<C>"), "Toggle continuous scrolling"),
+                    (key("<a>"), "Start or pause auto-scroll"),
+                    (key("<i>"), "Cover"),
+                    (key("<m>"), "Metadata"),
+                    (key("<s>"), "Reading time"),
+                    (key("<+> / <->"), "Faster / slower reading speed"),
+                ],
+            ),
+            (
+                "Search",
+                vec![
+                    (key("</>"), "Search the book"),
+                    (key("<n> / <N>"), "Next / previous match"),
+                    (key("<d>"), "Look up a word"),
+                ],
+            ),
+            (
+                "Notes",
+                vec![
+                    (key("<v>"), "Start or drop a selection"),
+                    (key("<n>"), "Add a note to the selection"),
+                ],
+            ),
+            (
+                "Misc",
+                vec![
+                    (key("<?>"), "This help"),
+                    (key("<c> / <Esc>"), "Close popups"),
+                    (keys.label(Action::Quit), "Quit"),
+                ],
+            ),
+        ]
+    }
+
+    fn render_help(&self, frame: &mut Frame) {
+        let popup_area = centered_rect(70, 90, frame.area());
+        self.clear_popup(frame, popup_area);
+        let accent = self.palette().accent;
+        let mut lines = Vec::new();
+        for (section, entries) in self.help_sections() {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(section.bold().underlined()));
+            for (key, action) in entries {
+                lines.push(Line::from(vec![
+                    format!("  {:<12}", key).fg(accent).bold(),
+                    action.into(),
+                ]));
+            }
+        }
+        let help = Paragraph::new(lines).block(
+            Block::default()
+                .title(" Help ")
+                .title(
+                    Title::from(" <C> close ")
+                        .alignment(Alignment::Center)
+                        .position(Position::Bottom),
+                )
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(help, popup_area);
+    }
+
+    fn render_links(&self, frame: &mut Frame) {
+        let popup_area = centered_rect(60, 70, frame.area());
+        self.clear_popup(frame, popup_area);
+        let block = Block::default()
+            .title(" Links ")
+            .title(
+                Title::from(" <Enter> follow <C> close ")
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL);
+
+        let links = self.links();
+        if links.is_empty() {
+            let empty = Paragraph::new("There are no links on this page.").block(block);
+            frame.render_widget(empty, popup_area);
+            return;
+        }
+
+        let items: Vec<Line> = links
+            .iter()
+            .enumerate()
+            .map(|(index, link)| {
+                let target = match link.spine_index {
+                    Some(spine_index) => format!("page {}", self.page_for_spine(spine_index) + 1),
+                    None => link.href.clone(),
+                };
+                Line::from(vec![
+                    format!("[{}] ", index + 1).bold(),
+                    link.label.clone().into(),
+                    format!(" -> {}", target).dim(),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.link_index));
+        StatefulWidget::render(list, popup_area, frame.buffer_mut(), &mut state);
+    }
+
+    /// Renders the table of contents as a scrolling list with a readability badge per entry
+    fn render_toc(&self, frame: &mut Frame) {
+        let popup_area = centered_rect(60, 70, frame.area());
+        self.clear_popup(frame, popup_area);
+        let block = Block::default()
+            .title(" Table of Contents ")
+            .title(
+                Title::from(" <Enter> jump <c>/<Esc> close ")
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .borders(Borders::ALL);
+
+        if self.toc.is_empty() {
+            let empty = Paragraph::new("This book has no table of contents.").block(block);
+            frame.render_widget(empty, popup_area);
+            return;
+        }
+
+        let items: Vec<Line> = self
+            .toc
+            .iter()
+            .map(|entry| {
+                let mut spans = vec![format!("{}{}", "  ".repeat(entry.depth), entry.label).into()];
+                let score = entry
+                    .spine_index
+                    .and_then(|index| self.readability.get(self.page_for_spine(index) as usize))
+                    .copied()
+                    .flatten();
+                if let Some(score) = score {
+                    spans.push(" ".into());
+                    spans.push(readability::badge(score));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::new().reversed());
+        let mut state = ListState::default().with_selected(Some(self.toc_index));
+        StatefulWidget::render(list, popup_area, frame.buffer_mut(), &mut state);
+    }
+
+    /// Identifier of the open book from its metadata, falling back to the file name
+    fn identifier(&self) -> String {
+        self.metadata
+            .get("identifier")
+            .and_then(|values| values.first())
+            .cloned()
+            .unwrap_or_else(|| queue::label(&self.path))
+    }
+
+    /// Shows a locator for the current position that can be shared and opened with --goto-locator
+    fn show_locator(&mut self) {
+        let locator = Locator {
+            identifier: self.identifier(),
+            spine_index: self.spine_index() as usize,
+            offset: self.scroll_offset,
+        };
+        self.popup_title = "Locator";
+        self.popup_text = Some(format!(
+            "{}\n\nOpen it with --goto-locator \n\n\n Press <C> to close pop-up!",
+            locator
+        ));
+    }
+
+    /// Jumps to a shared position, warning if it was made for a different book
+    fn go_to_locator(&mut self, locator: Locator) {
+        let identifier = self.identifier();
+        if locator.identifier != identifier {
+            self.popup_title = "Locator";
+            self.popup_text = Some(format!(
+                "This locator is for {} but the open book is {}, the position may not match.\n\n\n Press <C> to close pop-up!",
+                locator.identifier, identifier
+            ));
+        }
+        self.jump_to(self.page_for_spine(locator.spine_index), locator.offset);
+    }
+
+    /// Title of the chapter the reader is currently in
+    /// Label of the nearest table of contents entry at or before the current page,
+    /// or "Section N" when the book has no usable table of contents
+    fn chapter_title(&self) -> String {
+        match self.current_toc_index() {
+            Some(index) => self.toc[index].label.clone(),
+            None => format!("Section {}", self.spine_index() + 1),
+        }
+    }
+
+    /// Index of the last table of contents entry that starts at or before the current page
+    fn current_toc_index(&self) -> Option<usize> {
+        let spine_index = self.spine_index() as usize;
+        self.toc
+            .iter()
+            .rposition(|entry| entry.spine_index.is_some_and(|index| index <= spine_index))
+    }
+
+    /// Loads progress from a file
+    fn load_progress(&mut self) {
+        self.progress = progress::load();
+    }
+
+    /// Saves progress to a file if the position changed since it was last saved
+    /// A failed save is reported in a popup rather than ending the session
+    fn save_progress(&mut self) {
+        if self.path.is_empty() {
+            return;
+        }
+        let position = self.position();
+        if self.progress.get(&self.path) == Some(&position) {
+            return;
+        }
+        let page = position.page;
+        let previous = self.progress.insert(self.path.clone(), position);
+        let saved = progress::save(&self.progress);
+        self.report(saved);
+        if previous.is_some_and(|previous| previous.page != page) {
+            self.stats
+                .entry(self.path.clone())
+                .or_default()
+                .page_turned();
+            self.save_stats();
+        }
+    }
+
+    /// Adds the time since the last key press to the book's reading time
+    /// Gaps longer than `IDLE_TIMEOUT` only count up to the timeout, the reader has likely stepped away
+    fn record_reading_time(&mut self) {
+        if self.path.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let Some(last_activity) = self.last_activity else {
+            return;
+        };
+        let elapsed = now.duration_since(last_activity);
+        let seconds = if elapsed >= IDLE_TIMEOUT {
+            self.last_activity = Some(now);
+            IDLE_TIMEOUT.as_secs()
+        } else {
+            // Whole seconds are counted and the remainder carries over to the next key press
+            let seconds = elapsed.as_secs();
+            self.last_activity = Some(last_activity + Duration::from_secs(seconds));
+            seconds
+        };
+        self.stats
+            .entry(self.path.clone())
+            .or_default()
+            .total_seconds += seconds;
+    }
+
+    /// Saves reading stats, reporting failures in a popup
+    fn save_stats(&mut self) {
+        let saved = stats::save(&self.stats);
+        self.report(saved);
+    }
+
+    /// Calculate the estimated reading time in seconds for a number of words based on WPM
+    fn reading_time(&self, word_count: usize) -> u32 {
+        (word_count as f32 / self.wpm as f32 * 60.0).ceil() as u32
+    }
+
+    /// Words of the selected passage if there is one, otherwise of the current page
+    fn current_word_count(&self) -> usize {
+        match self.selected_text() {
+            Some(selected) => selected.split_whitespace().count(),
+            None => self.text.split_whitespace().count(),
+        }
+    }
+
+    /// Words from the top of the screen to the end of the book
+    fn remaining_word_count(&self) -> usize {
+        let on_page: usize = self
+            .text
+            .lines()
+            .skip(self.scroll_offset as usize)
+            .map(|line| line.split_whitespace().count())
+            .sum();
+        let later: usize = self
+            .content
+            .iter()
+            .skip(self.page as usize + 1)
+            .map(|page| page.text.split_whitespace().count())
+            .sum();
+        on_page + later
+    }
+
+    /// Show the estimated reading time for the selection or current page, the rest of the book
+    /// and the whole book in a popup
+    fn show_reading_time(&mut self) {
+        let scope = if self.selection.is_some() {
+            "selection"
+        } else {
+            "page"
+        };
+        let total: usize = self
+            .content
+            .iter()
+            .map(|page| page.text.split_whitespace().count())
+            .sum();
+        self.popup_title = "Reading Time";
+        self.popup_text = Some(format!(
+            "This {}: {} | Remaining: {} | Total: {} (WPM: {}) \n\n\n Press <C> to close pop-up!",
+            scope,
+            format_duration(self.reading_time(self.current_word_count())),
+            format_duration(self.reading_time(self.remaining_word_count())),
+            format_duration(self.reading_time(total)),
+            self.wpm
+        ));
+    }
+
+    /// Shows the document's metadata (e.g., author, title) in a popup
+    fn show_metadata(&mut self) {
+        let metadata_str = self.format_metadata();
+        self.show_metadata = Some(metadata_str);
+    }
+
+    /// Length of the book as aligned rows: words, content pages, words per page and reading time
+    /// Pages that count as filler with the current --min-words are left out of the page count
+    fn format_book_stats(&self) -> String {
+        let words: usize = self
+            .content
+            .iter()
+            .map(|page| page.text.split_whitespace().count())
+            .sum();
+        let content_pages = self
+            .content
+            .iter()
+            .filter(|page| !page.is_filler(self.min_words))
+            .count();
+        let rows = [
+            ("Words", words.to_string()),
+            ("Content pages", content_pages.to_string()),
+            ("Words per page", (words / content_pages.max(1)).to_string()),
+            (
+                "Reading time",
+                format!(
+                    "{} at {} WPM",
+                    format_duration(self.reading_time(words)),
+                    self.wpm
+                ),
+            ),
+        ];
+        let mut result = String::from("\n Book statistics\n");
+        for (label, value) in rows {
+            result.push_str(&format!("\n   {:<16}{}", label, value));
+        }
+        result.push('\n');
+        result
+    }
+
+    /// Helper function to format the metadata as a string
+    fn format_metadata(&self) -> String {
+        let mut result = String::new();
+        for (key, values) in &self.metadata {
+            result.push_str(&format!("\n {}:  {}\n", key, values.join(", ")));
+        }
+        let stats = self.stats.get(&self.path).cloned().unwrap_or_default();
+        result.push_str(&format!(
+            "\n Time read:  {}\n\n Pages today:  {}\n",
+            format_duration(stats.total_seconds.min(u32::MAX as u64) as u32),
+            stats.pages_today()
+        ));
+        result.push_str(&self.format_book_stats());
+        result.push_str("\n\n\nPress <C> to close pop-up!");
+        result
+    }
+}
+
+impl Widget for &App {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let heading = format!(
+            " {} ({}/{}) ",
+            self.chapter_title(),
+            self.page + 1,
+            self.pages.max(1)
+        );
+        let mut title_spans = vec![heading.bold()];
+        if let Some(Some(score)) = self.readability.get(self.page as usize) {
+            title_spans.push(readability::badge(*score));
+        }
+        let title = Title::from(Line::from(title_spans));
+        let palette = self.palette();
+        let keys = &self.config.keys;
+        let (previous_key, next_key) = if self.manga {
+            (
+                keys.label(Action::NextPage),
+                keys.label(Action::PreviousPage),
+            )
+        } else {
+            (
+                keys.label(Action::PreviousPage),
+                keys.label(Action::NextPage),
+            )
+        };
+        let instructions = Title::from(Line::from(vec![
+            " Previous page ".into(),
+            previous_key.fg(palette.accent).bold(),
+            " Next page ".into(),
+            next_key.fg(palette.accent).bold(),
+            " Scroll up ".into(),
+            keys.label(Action::ScrollUp).fg(palette.accent).bold(),
+            " Scroll down ".into(),
+            keys.label(Action::ScrollDown).fg(palette.accent).bold(),
+            " Help ".into(),
+            "<?>".fg(palette.accent).bold(),
+            " Quit ".into(),
+            format!("{} ", keys.label(Action::Quit))
+                .fg(palette.accent)
+                .bold(),
+        ]));
+        let mut block = Block::bordered()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .border_set(border::THICK)
+            .border_style(palette.border);
+        let fraction = self.fraction_read();
+        let filled = (fraction * 10.0).round() as usize;
+        let progress = Line::from(vec![
+            " ".into(),
+            "█".repeat(filled).fg(palette.text),
+            "░".repeat(10 - filled).dark_gray(),
+            format!(" {:.0}% ", fraction * 100.0).bold(),
+        ]);
+        block = block.title(Title::from(progress).alignment(Alignment::Left));
+        if !self.search_hits.is_empty() {
+            let status = format!(
+                " match {} of {} ",
+                self.search_index + 1,
+                self.search_hits.len()
+            );
+            block = block.title(Title::from(status.bold()).alignment(Alignment::Right));
+        }
+
+        self.view_height.set(area.height.saturating_sub(2));
+
+        let text_lines: Vec<Line> = self
+            .text
+            .lines() // Split text by newlines
+            .skip(self.scroll_offset as usize) // Skip lines based on scroll_offset
+            .take(area.height as usize) // Take only the visible lines
+            .enumerate()
+            .map(|(i, line)| {
+                let line_number = self.scroll_offset + i as u16;
+                let line = self.display_line(line);
+                let kind = self
+                    .content
+                    .get(self.page as usize)
+                    .map_or(LineKind::Body, |page| page.kind(line_number as usize));
+                let style = match kind {
+                    LineKind::Body => Style::new().fg(palette.text),
+                    LineKind::Figure => Style::new().fg(palette.text).bold(),
+                    LineKind::Caption => Style::new().fg(palette.text).dim().italic(),
+                    LineKind::Heading => Style::new().fg(palette.text).bold().underlined(),
+                };
+                let line = Line::from(self.highlight_matches(line, style));
+                let line = match kind {
+                    LineKind::Figure | LineKind::Caption => line.centered(),
+                    LineKind::Body | LineKind::Heading => line,
+                };
+                let pinned = line_number == self.ruler_line;
+                let selected = self.selection.is_some_and(|(anchor, end)| {
+                    (anchor.min(end)..=anchor.max(end)).contains(&line_number)
+                });
+                if selected {
+                    line.style(SELECTION_STYLE)
+                } else if self.ruler && self.config.ruler_mode == RulerMode::Follow && pinned {
+                    line.style(RULER_STYLE)
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let test_text = Text::from(text_lines);
+
+        let _counter_text = Text::from(vec![
+            Line::from(vec!["Page: ".into(), self.page.to_string().yellow()]),
+            Line::from(vec!["Path: ".into(), self.path.clone().yellow()]),
+            Line::from(vec!["text: ".into(), self.text.clone().yellow()]),
+        ]);
+
+        // Text is kept to the reading width and centered, leaving equal margins on wide terminals
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if let Some((_, Some(image))) = self
+            .page_image
+            .as_ref()
+            .filter(|_| book::is_blank(&self.text))
+        {
+            image.render_image(inner, buf);
+            return;
+        }
+        let width = inner.width.min(self.reading_width);
+        self.view_width.set(width);
+        let text_area = Rect::new(
+            inner.x + (inner.width - width) / 2,
+            inner.y,
+            width,
+            inner.height,
+        );
+        Paragraph::new(test_text)
+            .wrap(TEXT_WRAP)
+            .render(text_area, buf);
+
+        // A fixed ruler sits a third of the way down the text, whatever is scrolled beneath it
+        if self.ruler && self.config.ruler_mode == RulerMode::Fixed && text_area.height > 0 {
+            let row = text_area.y + text_area.height / 3;
+            let ruler = Rect::new(text_area.x, row, text_area.width, 1);
+            buf.set_style(ruler, RULER_STYLE);
+        }
+    }
+}
+
+/// Exports the notes of the book at `path` to a markdown file
+fn export_notes(path: &str, file: &str) -> Result<(), Error> {
+    let title = book::open(path)
+        .ok()
+        .and_then(|epub| epub.mdata("title"))
+        .unwrap_or_else(|| path.to_string());
+    notes::export(path, &title, file)
+}
+
+fn run(mut args: Args) -> Result<(), Error> {
+    // Saved state is keyed by book path, so make it independent of the working directory
+    if let Some(path) = args.path.as_mut() {
+        if let Ok(canonical) = std::fs::canonicalize(&*path) {
+            *path = canonical.to_string_lossy().into_owned();
+        }
+    }
+    // clap only accepts these options together with --path
+    let book_path = args.path.clone().unwrap_or_default();
+    if let Some(ref file) = args.export_notes {
+        return export_notes(&book_path, file);
+    }
+    if let Some(ref file) = args.export {
+        return export::export(&book_path, file);
+    }
+    if args.print_metadata {
+        return export::print_metadata(&book_path);
+    }
+    if args.add_to_queue {
+        let mut reading_queue = queue::load();
+        if queue::add(&mut reading_queue, &book_path) {
+            queue::save(&reading_queue)?;
+        }
+        return Ok(());
+    }
+
+    // Open the book and dictionary before entering raw mode so errors leave the terminal untouched
+    let epub = args.path.as_deref().map(book::open).transpose()?;
+    let dictionary = Dictionary::load(args.dictionary.as_deref(), args.dictionary_url.clone())?;
+    let mut terminal = ratatui::init();
+    // ratatui's panic hook restores the terminal, mouse reporting has to be turned off too
+    // or the shell is left printing escape codes
+    let restore_terminal = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stdout(), DisableMouseCapture);
+        restore_terminal(info);
+    }));
+    let app_result = execute!(stdout(), EnableMouseCapture)
+        .and_then(|_| terminal.clear())
+        .map_err(Error::from)
+        .and_then(|_| {
+            let mut app = App {
+                dictionary: Arc::new(dictionary),
+                ..App::default()
+            };
+            app.run(&mut terminal, epub, args)
+        });
+    // Leave the terminal as it was even if the app failed
+    let _ = execute!(stdout(), DisableMouseCapture);
+    ratatui::restore();
+    app_result
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reader showing a single page of `text` in a view of `width` by `height`
+    fn app_with_page(text: &str, width: u16, height: u16) -> App {
+        let app = App {
+            text: text.to_string(),
+            pages: 1,
+            content: vec![Page::default()],
+            ..App::default()
+        };
+        app.view_width.set(width);
+        app.view_height.set(height);
+        app
+    }
+
+    #[test]
+    fn scrolling_down_a_short_page_stays_at_the_top() {
+        let mut app = app_with_page("one\ntwo\nthree", 40, 10);
+        for _ in 0..20 {
+            app.scroll_down();
+            assert!(app.scroll_offset <= app.max_scroll_offset());
+        }
+        assert_eq!(app.scroll_offset, 0);
+    }
+
+    #[test]
+    fn scrolling_down_reaches_the_end_of_wrapped_lines() {
+        // Each paragraph wraps onto three rows of a 40 column view
+        let paragraph = "word ".repeat(20);
+        let text = [paragraph.trim(); 3].join("\n");
+        let mut app = app_with_page(&text, 40, 5);
+        for _ in 0..20 {
+            app.scroll_down();
+        }
+        // Only the last paragraph fits on screen, so it has to be scrolled to the top
+        assert_eq!(app.scroll_offset, 2);
+    }
+
+    #[test]
+    fn centered_rect_stays_inside_a_tiny_area() {
+        let area = Rect::new(3, 4, 1, 1);
+        for (x, y) in [(0, 0), (60, 20), (100, 100), (150, 300)] {
+            let popup = centered_rect(x, y, area);
+            assert!(popup.x >= area.x && popup.right() <= area.right());
+            assert!(popup.y >= area.y && popup.bottom() <= area.bottom());
+        }
+    }
+
+    #[test]
+    fn centered_rect_clamps_percentages_over_a_hundred() {
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(centered_rect(150, 200, area), area);
+    }
+
+    #[test]
+    fn a_reading_speed_of_zero_is_clamped() {
+        let mut app = App::default();
+        app.config.words_per_minute = 0;
+        app.book_settings.insert(
+            "book.epub".to_string(),
+            BookSettings {
+                words_per_minute: Some(0),
+                ..BookSettings::default()
+            },
+        );
+        app.apply_book_settings("other.epub");
+        assert_eq!(app.wpm, MIN_WPM);
+        app.apply_book_settings("book.epub");
+        assert_eq!(app.wpm, MIN_WPM);
+    }
+
+    #[test]
+    fn out_of_range_reading_widths_are_clamped() {
+        let mut app = App::default();
+        app.config.reading_width = 0;
+        app.book_settings.insert(
+            "book.epub".to_string(),
+            BookSettings {
+                reading_width: Some(u16::MAX),
+                ..BookSettings::default()
+            },
+        );
+        app.apply_book_settings("other.epub");
+        assert_eq!(app.reading_width, MIN_READING_WIDTH);
+        app.apply_book_settings("book.epub");
+        assert_eq!(app.reading_width, MAX_READING_WIDTH);
+    }
+
+    #[test]
+    fn the_mouse_wheel_scrolls_the_text_and_pauses_auto_scroll() {
+        let mut app = app_with_page(&"line\n".repeat(20), 40, 5);
+        app.auto_scroll = Some(Instant::now());
+        app.handle_mouse_scroll(true);
+        assert_eq!(app.scroll_offset, MOUSE_SCROLL_LINES);
+        assert!(app.auto_scroll.is_none());
+    }
+
+    #[test]
+    fn the_mouse_wheel_leaves_the_text_alone_behind_overlays() {
+        let mut app = app_with_page(&"line\n".repeat(20), 40, 5);
+        app.show_help = true;
+        app.handle_mouse_scroll(true);
+        app.show_help = false;
+        app.show_bookmarks = true;
+        app.handle_mouse_scroll(true);
+        assert_eq!(app.scroll_offset, 0);
+        assert!(app.show_bookmarks);
+    }
+
+    #[test]
+    fn looking_a_word_up_shows_its_definition_once_finished() {
+        let path = std::env::temp_dir().join("epub_reader_lookup_dictionary.json");
+        std::fs::write(&path, r#"{"ephemeral": "Lasting a very short time"}"#).unwrap();
+        let dictionary = Dictionary::load(Some(&path.to_string_lossy()), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut app = App {
+            dictionary: Arc::new(dictionary),
+            ..App::default()
+        };
+
+        app.look_up("Ephemeral,");
+        assert!(app.popup_text.is_some());
+        while !app.lookup.as_ref().is_some_and(Lookup::is_finished) {
+            std::thread::yield_now();
+        }
+        app.finish_lookup();
+        assert!(app.popup_text.is_none());
+        assert_eq!(
+            app.definition,
+            Some((
+                "Ephemeral,".to_string(),
+                "1. Lasting a very short time".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn changing_the_speed_from_the_keyboard_replaces_the_command_line_one() {
+        let mut app = App {
+            overrides: BookSettings {
+                words_per_minute: Some(400),
+                ..BookSettings::default()
+            },
+            ..App::default()
+        };
+        app.apply_book_settings("book.epub");
+        assert_eq!(app.wpm, 400);
+        app.config.keep_file = true;
+        app.change_wpm(WPM_STEP as i32);
+        app.apply_book_settings("other.epub");
+        assert_eq!(app.wpm, 410);
+    }
+
+    #[test]
+    fn searching_with_ascii_punctuation_matches_either_kind_of_quote() {
+        let mut page = Page::default();
+        page.text = "He said \u{201C}Hello\u{201D}.\nShe didn\u{2019}t.".to_string();
+        let mut app = App {
+            content: vec![page],
+            pages: 1,
+            ascii_punctuation: true,
+            ..App::default()
+        };
+        app.search("\"hello\"".to_string());
+        assert_eq!(app.search_hits, vec![(0, 0)]);
+        app.search("didn\u{2019}t".to_string());
+        assert_eq!(app.search_hits, vec![(0, 1)]);
+
+        let line = app.display_line("She didn\u{2019}t.");
+        let spans = app.highlight_matches(line, Style::new());
+        assert_eq!(spans[1].content, "didn't");
+    }
 }
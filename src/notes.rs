@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::{paths, Error};
+
+const NOTES_FILE: &str = "notes.json";
+
+/// A note attached to a quoted passage of a book
+/// The quote is stored alongside the note so exports don't need the book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub page: u16,
+    pub line: u16,
+    pub chapter: String,
+    pub quote: String,
+    pub note: String,
+}
+
+/// Loads all annotations, keyed by book path
+pub fn load() -> HashMap<String, Vec<Annotation>> {
+    paths::load_json(NOTES_FILE)
+}
+
+/// Saves all annotations
+pub fn save(notes: &HashMap<String, Vec<Annotation>>) -> Result<(), Error> {
+    paths::save_json(NOTES_FILE, notes)
+}
+
+/// Renders the annotations of a book as markdown, grouped by chapter in reading order
+pub fn to_markdown(title: &str, annotations: &[Annotation]) -> String {
+    let mut sorted: Vec<&Annotation> = annotations.iter().collect();
+    sorted.sort_by_key(|annotation| (annotation.page, annotation.line));
+
+    let mut markdown = format!("# Notes: {}\n", title);
+    let mut chapter = None;
+    for annotation in sorted {
+        if chapter != Some(&annotation.chapter) {
+            markdown.push_str(&format!("\n## {}\n", annotation.chapter));
+            chapter = Some(&annotation.chapter);
+        }
+        markdown.push('\n');
+        for line in annotation.quote.lines() {
+            markdown.push_str(&format!("> {}\n", line));
+        }
+        markdown.push_str(&format!("\n{}\n", annotation.note));
+    }
+    markdown
+}
+
+/// Writes the notes of a book to a markdown file
+pub fn export(path: &str, title: &str, file: &str) -> Result<(), Error> {
+    let notes = load();
+    let annotations = notes.get(path).map(Vec::as_slice).unwrap_or_default();
+    fs::write(file, to_markdown(title, annotations)).map_err(|source| Error::ExportFailed {
+        file: file.to_string(),
+        source,
+    })
+}
@@ -0,0 +1,54 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::Error;
+
+const APP_DIR: &str = "epub_reader";
+
+/// Location of a file holding saved state, e.g. `~/.config/epub_reader/progress.json`
+/// Uses `$XDG_CONFIG_HOME` (or the platform equivalent) and falls back to the
+/// current directory if no config directory can be determined
+pub fn state_file(name: &str) -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join(APP_DIR).join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Reads a state file, `None` if it doesn't exist or can't be read
+pub fn read(name: &str) -> Option<String> {
+    fs::read_to_string(state_file(name)).ok()
+}
+
+/// Writes a state file, creating its directory if needed
+/// The data goes to a temporary file first and is renamed over the old file,
+/// so an interrupted write never leaves a truncated file behind
+pub fn write(name: &str, data: String) -> Result<(), Error> {
+    let path = state_file(name);
+    let failed = |source| Error::StateIo {
+        file: path.display().to_string(),
+        source,
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(failed)?;
+    }
+    let mut temporary = path.clone().into_os_string();
+    temporary.push(".tmp");
+    fs::write(&temporary, data).map_err(failed)?;
+    fs::rename(&temporary, &path).map_err(failed)
+}
+
+/// Reads a JSON state file, the default value if it doesn't exist or can't be parsed
+pub fn load_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    read(name)
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Writes a value to a JSON state file
+pub fn save_json<T: Serialize + ?Sized>(name: &str, value: &T) -> Result<(), Error> {
+    let data = serde_json::to_string(value).unwrap();
+    write(name, data)
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{paths, Error};
+
+const PROGRESS_FILE: &str = "progress.json";
+
+/// Number of characters of a line kept in a snippet
+const SNIPPET_LENGTH: usize = 40;
+
+/// Saved reading position of a book
+/// `page` is the spine index and `offset` the scroll offset within it
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadingPosition {
+    pub page: u16,
+    pub offset: u16,
+    /// Start of the first line of text at `offset`, to find it again when the page's lines shift
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl ReadingPosition {
+    /// Position at `offset` of a page with the given text, remembering a snippet of the line there
+    pub fn new(page: u16, offset: u16, text: &str) -> Self {
+        let snippet = text.lines().skip(offset as usize).find_map(fingerprint);
+        ReadingPosition {
+            page,
+            offset,
+            snippet,
+        }
+    }
+
+    /// Offset of the line of `text` the snippet was taken from, the match nearest to the saved
+    /// offset wins, falling back to the saved offset if there is no snippet or it isn't found
+    pub fn offset_in(&self, text: &str) -> u16 {
+        let Some(ref snippet) = self.snippet else {
+            return self.offset;
+        };
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| fingerprint(line).as_ref() == Some(snippet))
+            .map(|(index, _)| index as u16)
+            .min_by_key(|&index| index.abs_diff(self.offset))
+            .unwrap_or(self.offset)
+    }
+}
+
+/// Lowercased letters and digits from the start of a line, `None` for lines without any
+/// Punctuation, markers and spacing are left out so extraction changes don't affect it
+fn fingerprint(line: &str) -> Option<String> {
+    let fingerprint: String = line
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .take(SNIPPET_LENGTH)
+        .collect();
+    (!fingerprint.is_empty()).then_some(fingerprint)
+}
+
+/// Loads the saved position of every book, keyed by book path
+/// Files from older versions only stored the page, those load with an offset of 0
+pub fn load() -> HashMap<String, ReadingPosition> {
+    let Some(data) = paths::read(PROGRESS_FILE) else {
+        return HashMap::new();
+    };
+    if let Ok(progress) = serde_json::from_str(&data) {
+        return progress;
+    }
+    serde_json::from_str::<HashMap<String, u16>>(&data)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(path, page)| {
+            let position = ReadingPosition {
+                page,
+                ..ReadingPosition::default()
+            };
+            (path, position)
+        })
+        .collect()
+}
+
+/// Saves the position of every book
+pub fn save(progress: &HashMap<String, ReadingPosition>) -> Result<(), Error> {
+    paths::save_json(PROGRESS_FILE, progress)
+}
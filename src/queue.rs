@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use crate::{paths, Error};
+
+const QUEUE_FILE: &str = "queue.json";
+
+/// Loads the reading queue, an ordered list of book paths
+pub fn load() -> Vec<String> {
+    paths::load_json(QUEUE_FILE)
+}
+
+/// Saves the reading queue
+pub fn save(queue: &[String]) -> Result<(), Error> {
+    paths::save_json(QUEUE_FILE, queue)
+}
+
+/// Adds a book to the end of the queue unless it is already queued
+pub fn add(queue: &mut Vec<String>, path: &str) -> bool {
+    if queue.iter().any(|queued| queued == path) {
+        return false;
+    }
+    queue.push(path.to_string());
+    true
+}
+
+/// Label shown for a queued book, flagging files that no longer exist
+pub fn label(path: &str) -> String {
+    let name = Path::new(path)
+        .file_name()
+        .map_or(path.into(), |name| name.to_string_lossy());
+    if Path::new(path).exists() {
+        name.into_owned()
+    } else {
+        format!("{} (missing)", name)
+    }
+}
@@ -0,0 +1,111 @@
+use ratatui::style::{Color, Stylize};
+use ratatui::text::Span;
+
+use crate::Page;
+
+/// Minimum number of words a page needs before a score is worth showing,
+/// very short pages (title pages, dividers) give meaningless results
+const MIN_WORDS: usize = 30;
+
+/// Computes the Flesch reading-ease score for every page of the book
+/// Returns `None` for pages that are too short, or for every page when the book is not English
+pub fn score_pages(content: &[Page], metadata_language: Option<&str>) -> Vec<Option<f32>> {
+    let english = match metadata_language {
+        Some(language) => language.to_lowercase().starts_with("en"),
+        None => true,
+    };
+    content
+        .iter()
+        .map(|page| {
+            if english {
+                flesch_reading_ease(&page.text)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Flesch reading-ease score of a block of text
+/// Higher is easier, roughly 0 (very difficult) to 100 (very easy)
+pub fn flesch_reading_ease(text: &str) -> Option<f32> {
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() < MIN_WORDS || !looks_english(&words) {
+        return None;
+    }
+
+    let sentences = count_sentences(text).max(1);
+    let syllables: usize = words.iter().map(|word| count_syllables(word)).sum();
+
+    let words_per_sentence = words.len() as f32 / sentences as f32;
+    let syllables_per_word = syllables as f32 / words.len() as f32;
+    let score = 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word;
+    Some(score.clamp(0.0, 100.0))
+}
+
+/// Compact badge describing a score, coloured by difficulty band
+pub fn badge(score: f32) -> Span<'static> {
+    let label = format!(" Ease {:.0} ", score);
+    let color = if score >= 70.0 {
+        Color::Green
+    } else if score >= 50.0 {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    label.fg(color).bold()
+}
+
+/// The syllable heuristics only hold for latin script text,
+/// so pages that are mostly other scripts are skipped
+fn looks_english(words: &[&str]) -> bool {
+    let ascii_words = words.iter().filter(|word| word.is_ascii()).count();
+    ascii_words * 10 >= words.len() * 9
+}
+
+/// Counts sentence terminators, treating runs like "?!" or "..." as one
+fn count_sentences(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_terminator = false;
+    for c in text.chars() {
+        if matches!(c, '.' | '!' | '?') {
+            if !in_terminator {
+                count += 1;
+            }
+            in_terminator = true;
+        } else {
+            in_terminator = false;
+        }
+    }
+    count
+}
+
+/// Estimates syllables by counting vowel groups, ignoring a silent trailing 'e'
+fn count_syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let chars: Vec<char> = word.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    if chars.is_empty() {
+        return 0;
+    }
+
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for &c in &chars {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    let len = chars.len();
+    if len > 2 && chars[len - 1] == 'e' && chars[len - 2] != 'l' && !is_vowel(chars[len - 2]) {
+        count -= 1;
+    }
+    count.max(1)
+}
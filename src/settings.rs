@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::theme::Theme;
+use crate::{paths, Error};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Settings last used for a book, left out ones fall back to `config.toml`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BookSettings {
+    pub words_per_minute: Option<u16>,
+    pub reading_width: Option<u16>,
+    pub theme: Option<Theme>,
+}
+
+/// Loads the settings of every book, keyed by book path
+pub fn load() -> HashMap<String, BookSettings> {
+    paths::load_json(SETTINGS_FILE)
+}
+
+/// Saves the settings of every book
+pub fn save(settings: &HashMap<String, BookSettings>) -> Result<(), Error> {
+    paths::save_json(SETTINGS_FILE, settings)
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{paths, Error};
+
+const STATS_FILE: &str = "stats.json";
+
+/// Time spent reading a book and pages turned today
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BookStats {
+    pub total_seconds: u64,
+    /// Day `pages_today` was counted on, in days since the Unix epoch
+    day: u64,
+    pages_today: u32,
+}
+
+impl BookStats {
+    /// Counts a page turn towards today's total, starting over on a new day
+    pub fn page_turned(&mut self) {
+        let today = today();
+        if self.day != today {
+            self.day = today;
+            self.pages_today = 0;
+        }
+        self.pages_today += 1;
+    }
+
+    pub fn pages_today(&self) -> u32 {
+        if self.day == today() {
+            self.pages_today
+        } else {
+            0
+        }
+    }
+}
+
+/// Current day in days since the Unix epoch (UTC)
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400)
+}
+
+/// Loads the reading stats of every book, keyed by book path
+pub fn load() -> HashMap<String, BookStats> {
+    paths::load_json(STATS_FILE)
+}
+
+/// Saves the reading stats of every book
+pub fn save(stats: &HashMap<String, BookStats>) -> Result<(), Error> {
+    paths::save_json(STATS_FILE, stats)
+}
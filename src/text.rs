@@ -0,0 +1,69 @@
+use std::ops::Range;
+
+/// Replaces typographic punctuation with plain ASCII equivalents
+/// for terminals and fonts that render curly quotes or dashes poorly
+pub fn ascii_punctuation(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{00AB}' | '\u{00BB}' => ascii.push('"'),
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{2032}' => ascii.push('\''),
+            '\u{2014}' | '\u{2015}' => ascii.push_str("--"),
+            '\u{2013}' | '\u{2010}' | '\u{2011}' | '\u{2212}' => ascii.push('-'),
+            '\u{2026}' => ascii.push_str("..."),
+            '\u{00A0}' | '\u{2009}' | '\u{202F}' => ascii.push(' '),
+            _ => ascii.push(c),
+        }
+    }
+    ascii
+}
+
+/// Byte ranges of the non-overlapping matches of `needle` in `text`, ignoring case
+/// `needle` must already be lowercase
+pub fn find_matches(text: &str, needle: &str) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    if needle.is_empty() {
+        return matches;
+    }
+    let mut next = 0;
+    for (start, _) in text.char_indices() {
+        if start < next {
+            continue;
+        }
+        // Lowercase one character at a time, some change length when lowercased
+        let mut lowered = String::new();
+        for (offset, c) in text[start..].char_indices() {
+            if !needle.starts_with(&lowered) {
+                break;
+            }
+            if lowered.len() >= needle.len() {
+                break;
+            }
+            lowered.extend(c.to_lowercase());
+            if lowered == needle {
+                next = start + offset + c.len_utf8();
+                matches.push(start..next);
+                break;
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typographic_punctuation_becomes_ascii() {
+        let text =
+            "\u{201C}It\u{2019}s \u{2018}fine\u{2019}\u{201D} \u{2014} pages 3\u{2013}5\u{2026}";
+        assert_eq!(ascii_punctuation(text), "\"It's 'fine'\" -- pages 3-5...");
+    }
+
+    #[test]
+    fn other_characters_are_left_alone() {
+        let text = "Caf\u{e9} na\u{ef}ve, plain \"quotes\" -- already";
+        assert_eq!(ascii_punctuation(text), text);
+    }
+}
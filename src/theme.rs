@@ -0,0 +1,68 @@
+use clap::ValueEnum;
+use ratatui::style::{Color, Style};
+use serde::{Deserialize, Serialize};
+
+/// Color scheme of the reader
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Yellow text on the terminal's own background
+    #[default]
+    Default,
+    /// Light text on a black background
+    Dark,
+    /// Dark text on a white background
+    Light,
+}
+
+/// Colors used to draw the reader for a theme
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// Background and default foreground of the whole screen
+    pub base: Style,
+    /// Color of the book's text, the splash screen and progress bars
+    pub text: Color,
+    /// Color of borders around the text
+    pub border: Color,
+    /// Color of key hints
+    pub accent: Color,
+    /// Background and foreground of popups
+    pub popup: Style,
+}
+
+impl Theme {
+    /// The next theme, cycling through all of them
+    pub fn toggled(self) -> Self {
+        match self {
+            Theme::Default => Theme::Dark,
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Default,
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Default => Palette {
+                base: Style::new(),
+                text: Color::Yellow,
+                border: Color::Reset,
+                accent: Color::Blue,
+                popup: Style::new(),
+            },
+            Theme::Dark => Palette {
+                base: Style::new().fg(Color::Gray).bg(Color::Black),
+                text: Color::Gray,
+                border: Color::DarkGray,
+                accent: Color::Cyan,
+                popup: Style::new().fg(Color::White).bg(Color::Black),
+            },
+            Theme::Light => Palette {
+                base: Style::new().fg(Color::Black).bg(Color::White),
+                text: Color::Black,
+                border: Color::Gray,
+                accent: Color::Blue,
+                popup: Style::new().fg(Color::Black).bg(Color::White),
+            },
+        }
+    }
+}